@@ -0,0 +1,327 @@
+use crate::config::ApiConfig;
+use crate::events::{FleetEvent, PaneRef};
+use crate::model::{
+    ActivityState, AppState, AttentionState, CommandFinished, PaneCapture, PaneStatus,
+};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::{broadcast, mpsc, oneshot};
+use tokio::task::JoinHandle;
+
+const MAX_HEADER_BYTES: usize = 8 * 1024;
+const SSE_KEEPALIVE: Duration = Duration::from_secs(15);
+
+#[derive(Clone, Debug, Serialize)]
+pub struct StateSnapshot {
+    generated_at: String,
+    focused: usize,
+    hosts: Vec<HostSnapshot>,
+    panes: Vec<PaneSnapshot>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct HostSnapshot {
+    name: String,
+    targets: Vec<String>,
+    color: Option<String>,
+    tags: Vec<String>,
+    status: &'static str,
+    target: Option<String>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+struct PaneSnapshot {
+    #[serde(flatten)]
+    pane: PaneRef,
+    status: PaneStatus,
+    activity: ActivityState,
+    attention: AttentionState,
+    error: Option<String>,
+    last_update_ms_ago: Option<u64>,
+    last_change_ms_ago: Option<u64>,
+    running: Option<String>,
+    finished: Option<CommandFinished>,
+    capture: Option<PaneCapture>,
+}
+
+impl StateSnapshot {
+    pub fn from_state(state: &AppState) -> Self {
+        let hosts = state
+            .config
+            .hosts
+            .iter()
+            .map(|host| {
+                let resolved = state.host_targets.get(&host.name);
+                HostSnapshot {
+                    name: host.name.clone(),
+                    targets: host.targets.clone(),
+                    color: host.color.clone(),
+                    tags: host.tags.clone().unwrap_or_default(),
+                    status: match resolved {
+                        Some(Some(_)) => "up",
+                        Some(None) => "down",
+                        None => "unknown",
+                    },
+                    target: resolved.cloned().flatten(),
+                }
+            })
+            .collect();
+        let panes = state
+            .panes
+            .iter()
+            .enumerate()
+            .map(|(index, pane)| PaneSnapshot {
+                pane: PaneRef::new(index, pane),
+                status: pane.status.clone(),
+                activity: state
+                    .activity_states
+                    .get(index)
+                    .copied()
+                    .unwrap_or(ActivityState::Quiet),
+                attention: state
+                    .attention
+                    .get(index)
+                    .copied()
+                    .unwrap_or(AttentionState::None),
+                error: pane.error.clone(),
+                last_update_ms_ago: pane
+                    .last_update
+                    .map(|at| at.elapsed().as_millis() as u64),
+                last_change_ms_ago: pane
+                    .last_change
+                    .map(|at| at.elapsed().as_millis() as u64),
+                running: pane.running.as_ref().map(|(command, _)| command.clone()),
+                finished: pane.finished.clone(),
+                capture: pane.last_capture.clone(),
+            })
+            .collect();
+        Self {
+            generated_at: chrono::Local::now()
+                .to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+            focused: state.focused,
+            hosts,
+            panes,
+        }
+    }
+}
+
+pub type SnapshotRequest = oneshot::Sender<Arc<StateSnapshot>>;
+
+pub struct ApiServer {
+    requests_rx: mpsc::Receiver<SnapshotRequest>,
+    events_tx: broadcast::Sender<FleetEvent>,
+    task: JoinHandle<()>,
+}
+
+impl ApiServer {
+    pub async fn start(config: &ApiConfig) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let addr: SocketAddr = config
+            .bind
+            .parse()
+            .with_context(|| format!("Invalid api.bind address: {}", config.bind))?;
+        if !addr.ip().is_loopback() {
+            return Err(anyhow!(
+                "api.bind must be a loopback address (got {})",
+                config.bind
+            ));
+        }
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Unable to listen on {addr}"))?;
+        let (requests_tx, requests_rx) = mpsc::channel(16);
+        let (events_tx, _) = broadcast::channel(256);
+        let events_for_accept = events_tx.clone();
+        let task = tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let requests_tx = requests_tx.clone();
+                let events_rx = events_for_accept.subscribe();
+                tokio::spawn(async move {
+                    let _ = handle_connection(stream, addr, requests_tx, events_rx).await;
+                });
+            }
+        });
+        Ok(Some(Self {
+            requests_rx,
+            events_tx,
+            task,
+        }))
+    }
+
+    // Snapshots include every capture, so they are only built when a client asks for one.
+    pub async fn next_request(&mut self) -> Option<SnapshotRequest> {
+        self.requests_rx.recv().await
+    }
+
+    pub fn respond(&self, state: &AppState, request: SnapshotRequest) {
+        let _ = request.send(Arc::new(StateSnapshot::from_state(state)));
+    }
+
+    pub fn publish(&self, events: &[FleetEvent]) {
+        for event in events {
+            let _ = self.events_tx.send(event.clone());
+        }
+    }
+}
+
+impl Drop for ApiServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+pub async fn next_request(api: Option<&mut ApiServer>) -> Option<SnapshotRequest> {
+    match api {
+        Some(api) => api.next_request().await,
+        None => std::future::pending().await,
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    addr: SocketAddr,
+    requests_tx: mpsc::Sender<SnapshotRequest>,
+    events_rx: broadcast::Receiver<FleetEvent>,
+) -> Result<()> {
+    let mut reader = BufReader::new(stream.take(MAX_HEADER_BYTES as u64));
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line).await?;
+    let mut host = None;
+    let mut complete = false;
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line).await? == 0 {
+            break;
+        }
+        if line == "\r\n" || line == "\n" {
+            complete = true;
+            break;
+        }
+        if let Some((name, value)) = line.split_once(':')
+            && name.trim().eq_ignore_ascii_case("host")
+        {
+            host = Some(value.trim().to_ascii_lowercase());
+        }
+    }
+    let exhausted = reader.get_ref().limit() == 0;
+    let mut stream = reader.into_inner().into_inner();
+    if !complete {
+        if exhausted {
+            return write_json(&mut stream, 431, &error_body("Request headers too large")).await;
+        }
+        return Ok(());
+    }
+    // A browser page that rebinds its own name to 127.0.0.1 still sends that name as Host.
+    if !host.as_deref().is_some_and(|host| allowed_host(host, addr)) {
+        return write_json(&mut stream, 403, &error_body("Host not allowed")).await;
+    }
+
+    let mut parts = request_line.split_whitespace();
+    let method = parts.next().unwrap_or("");
+    let target = parts.next().unwrap_or("");
+    let path = target.split('?').next().unwrap_or("").trim_end_matches('/');
+    if method != "GET" {
+        return write_json(&mut stream, 405, &error_body("Only GET is supported")).await;
+    }
+
+    let (reply_tx, reply_rx) = oneshot::channel();
+    requests_tx.send(reply_tx).await?;
+    let Ok(snapshot) = reply_rx.await else {
+        return write_json(&mut stream, 503, &error_body("FleetMux is shutting down")).await;
+    };
+    match path {
+        "/api/state" => write_json(&mut stream, 200, &serde_json::to_value(&*snapshot)?).await,
+        "/api/hosts" => write_json(&mut stream, 200, &serde_json::to_value(&snapshot.hosts)?).await,
+        "/api/panes" => write_json(&mut stream, 200, &serde_json::to_value(&snapshot.panes)?).await,
+        "/api/events" => stream_events(stream, snapshot, events_rx).await,
+        _ => {
+            if let Some(index) = path.strip_prefix("/api/panes/") {
+                let pane = index
+                    .parse::<usize>()
+                    .ok()
+                    .and_then(|index| snapshot.panes.get(index));
+                return match pane {
+                    Some(pane) => write_json(&mut stream, 200, &serde_json::to_value(pane)?).await,
+                    None => write_json(&mut stream, 404, &error_body("Unknown pane")).await,
+                };
+            }
+            write_json(&mut stream, 404, &error_body("Not found")).await
+        }
+    }
+}
+
+fn allowed_host(host: &str, addr: SocketAddr) -> bool {
+    let port = addr.port();
+    host == addr.to_string() || host == format!("localhost:{port}")
+}
+
+async fn stream_events(
+    mut stream: TcpStream,
+    snapshot: Arc<StateSnapshot>,
+    mut events_rx: broadcast::Receiver<FleetEvent>,
+) -> Result<()> {
+    let head = "HTTP/1.1 200 OK\r\n\
+                Content-Type: text/event-stream\r\n\
+                Cache-Control: no-cache\r\n\
+                Connection: keep-alive\r\n\r\n";
+    stream.write_all(head.as_bytes()).await?;
+    let snapshot = serde_json::to_string(&*snapshot)?;
+    stream
+        .write_all(format!("event: snapshot\ndata: {snapshot}\n\n").as_bytes())
+        .await?;
+    loop {
+        let message = tokio::select! {
+            received = events_rx.recv() => match received {
+                Ok(event) => {
+                    let value = serde_json::to_value(&event)?;
+                    let kind = value
+                        .get("event")
+                        .and_then(|kind| kind.as_str())
+                        .unwrap_or("message")
+                        .to_string();
+                    format!("event: {kind}\ndata: {value}\n\n")
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    format!(": skipped {skipped} events\n\n")
+                }
+                Err(broadcast::error::RecvError::Closed) => return Ok(()),
+            },
+            _ = tokio::time::sleep(SSE_KEEPALIVE) => ": keepalive\n\n".to_string(),
+        };
+        stream.write_all(message.as_bytes()).await?;
+    }
+}
+
+async fn write_json(stream: &mut TcpStream, status: u16, body: &serde_json::Value) -> Result<()> {
+    let body = serde_json::to_string_pretty(body)?;
+    let reason = match status {
+        200 => "OK",
+        404 => "Not Found",
+        403 => "Forbidden",
+        405 => "Method Not Allowed",
+        431 => "Request Header Fields Too Large",
+        503 => "Service Unavailable",
+        _ => "Error",
+    };
+    let response = format!(
+        "HTTP/1.1 {status} {reason}\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Connection: close\r\n\r\n{body}",
+        body.len()
+    );
+    stream.write_all(response.as_bytes()).await?;
+    stream.shutdown().await?;
+    Ok(())
+}
+
+fn error_body(message: &str) -> serde_json::Value {
+    serde_json::json!({ "error": message })
+}
@@ -0,0 +1,409 @@
+use anyhow::{Context, Result, anyhow};
+use std::path::PathBuf;
+
+const COMMANDS: &[(&str, &str, &str)] = &[
+    (
+        "dash",
+        "fleetmux [dash]",
+        "Open the dashboard (the default)",
+    ),
+    ("setup", "fleetmux setup", "Pick hosts and panes to track"),
+    (
+        "doctor",
+        "fleetmux doctor",
+        "Check ssh, tmux and notifications on every host",
+    ),
+    (
+        "notify-test",
+        "fleetmux notify-test",
+        "Send a test notification to every channel",
+    ),
+    (
+        "list",
+        "fleetmux list [--json]",
+        "Print every pane across the fleet",
+    ),
+    (
+        "capture",
+        "fleetmux capture <label | host pane-id> [--lines <n>]",
+        "Print the current contents of a pane",
+    ),
+    (
+        "attach",
+        "fleetmux attach <label | host pane-id>",
+        "Attach to the tmux session of a pane",
+    ),
+    (
+        "watch",
+        "fleetmux watch [--json]",
+        "Print pane events without the dashboard",
+    ),
+    (
+        "replay",
+        "fleetmux replay <file> [--speed <n>] [--idle-limit <sec>]",
+        "Play back a recording",
+    ),
+    (
+        "config",
+        "fleetmux config check|migrate [path]",
+        "Validate the config or upgrade it to the current version",
+    ),
+    (
+        "hosts",
+        "fleetmux hosts import-ssh [--group] [--all] [--ssh-config <path>]",
+        "Import hosts from ~/.ssh/config",
+    ),
+];
+
+const OPTIONS: &str = "      --config <path>    Config file to use
+      --profile <name>   Use profiles/<name>.toml from the config directory
+      --refresh-ms <ms>  Poll panes every <ms> milliseconds for this run
+      --no-ansi          Capture panes without colors for this run
+  -h, --help             Print help
+  -V, --version          Print version
+";
+
+pub struct Cli {
+    pub config: Option<String>,
+    pub profile: Option<String>,
+    pub refresh_ms: Option<u64>,
+    pub no_ansi: bool,
+    pub command: Command,
+}
+
+pub enum Command {
+    Dash,
+    Setup,
+    Doctor,
+    NotifyTest,
+    List {
+        json: bool,
+    },
+    Capture {
+        pane: PaneRef,
+        lines: Option<usize>,
+    },
+    Attach {
+        pane: PaneRef,
+    },
+    Watch {
+        json: bool,
+    },
+    Replay {
+        path: PathBuf,
+        speed: f64,
+        idle_limit: Option<f64>,
+    },
+    ConfigCheck {
+        path: Option<PathBuf>,
+    },
+    ConfigMigrate {
+        path: Option<PathBuf>,
+    },
+    ImportSsh {
+        group: bool,
+        all: bool,
+        ssh_config: Option<String>,
+    },
+    Help(Option<&'static str>),
+    Version,
+}
+
+pub enum PaneRef {
+    Label(String),
+    Pane { host: String, pane_id: String },
+}
+
+pub fn parse(args: &[String]) -> Result<Cli> {
+    let mut cli = Cli {
+        config: None,
+        profile: None,
+        refresh_ms: None,
+        no_ansi: false,
+        command: Command::Dash,
+    };
+    let mut help = false;
+    let mut version = false;
+    let mut name = None;
+    let mut rest = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let (flag, inline) = match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => (flag, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline
+                .clone()
+                .or_else(|| iter.next().cloned())
+                .ok_or_else(|| anyhow!("{flag} requires a value"))
+        };
+        match flag {
+            "--config" => cli.config = Some(value()?),
+            "--profile" => cli.profile = Some(value()?),
+            "--refresh-ms" => {
+                let value = value()?;
+                let refresh_ms =
+                    value
+                        .parse::<u64>()
+                        .ok()
+                        .filter(|ms| *ms > 0)
+                        .ok_or_else(|| {
+                            anyhow!("--refresh-ms must be a positive number, got {value:?}")
+                        })?;
+                cli.refresh_ms = Some(refresh_ms);
+            }
+            "--no-ansi" => cli.no_ansi = true,
+            "-h" | "--help" => help = true,
+            "-V" | "--version" => version = true,
+            _ if name.is_none() && !arg.starts_with('-') => name = Some(arg.as_str()),
+            _ => rest.push(arg.as_str()),
+        }
+    }
+    if version {
+        cli.command = Command::Version;
+        return Ok(cli);
+    }
+    let Some(name) = name else {
+        if let Some(arg) = rest.first() {
+            return Err(anyhow!(
+                "Unknown option: {arg}\n\nRun `fleetmux --help` for usage."
+            ));
+        }
+        cli.command = if help {
+            Command::Help(None)
+        } else {
+            Command::Dash
+        };
+        return Ok(cli);
+    };
+    if name == "help" {
+        let topic = rest.first().copied();
+        return match topic {
+            None => Ok(Cli {
+                command: Command::Help(None),
+                ..cli
+            }),
+            Some(topic) => match COMMANDS.iter().find(|(command, _, _)| *command == topic) {
+                Some((command, _, _)) => Ok(Cli {
+                    command: Command::Help(Some(command)),
+                    ..cli
+                }),
+                None => Err(anyhow!(
+                    "Unknown command: {topic}\n\nRun `fleetmux --help` for a list of commands."
+                )),
+            },
+        };
+    }
+    let Some((name, usage, _)) = COMMANDS.iter().find(|(command, _, _)| *command == name) else {
+        let hint = suggest(name)
+            .map(|command| format!("\n\nDid you mean `fleetmux {command}`?"))
+            .unwrap_or_default();
+        return Err(anyhow!(
+            "Unknown command: {name}{hint}\n\nRun `fleetmux --help` for a list of commands."
+        ));
+    };
+    if help {
+        cli.command = Command::Help(Some(name));
+        return Ok(cli);
+    }
+    cli.command = parse_command(name, &rest).map_err(|err| anyhow!("{err}\n\nUsage: {usage}"))?;
+    Ok(cli)
+}
+
+fn parse_command(name: &str, args: &[&str]) -> Result<Command> {
+    let mut flags = Vec::new();
+    let mut positional = Vec::new();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        match arg.split_once('=') {
+            Some((flag, value)) if flag.starts_with("--") => {
+                flags.push((flag, Some(value.to_string())))
+            }
+            _ if arg.starts_with("--") => {
+                let takes_value = matches!(
+                    *arg,
+                    "--lines" | "--speed" | "--idle-limit" | "--ssh-config"
+                );
+                let value = if takes_value {
+                    Some(
+                        iter.next()
+                            .map(|value| value.to_string())
+                            .ok_or_else(|| anyhow!("{arg} requires a value"))?,
+                    )
+                } else {
+                    None
+                };
+                flags.push((arg, value));
+            }
+            _ => positional.push(*arg),
+        }
+    }
+    let mut flag = |wanted: &str| {
+        flags
+            .iter()
+            .position(|(flag, _)| *flag == wanted)
+            .map(|index| flags.remove(index).1)
+    };
+    let command = match name {
+        "dash" => Command::Dash,
+        "setup" => Command::Setup,
+        "doctor" => Command::Doctor,
+        "notify-test" => Command::NotifyTest,
+        "list" => Command::List {
+            json: flag("--json").is_some(),
+        },
+        "watch" => Command::Watch {
+            json: flag("--json").is_some(),
+        },
+        "capture" => Command::Capture {
+            lines: flag("--lines")
+                .flatten()
+                .map(|value| parse_number(&value, "--lines"))
+                .transpose()?,
+            pane: pane_ref(&mut positional)?,
+        },
+        "attach" => Command::Attach {
+            pane: pane_ref(&mut positional)?,
+        },
+        "replay" => {
+            if positional.is_empty() {
+                return Err(anyhow!("Missing recording file"));
+            }
+            Command::Replay {
+                path: PathBuf::from(positional.remove(0)),
+                speed: flag("--speed")
+                    .flatten()
+                    .map(|value| parse_number(&value, "--speed"))
+                    .transpose()?
+                    .unwrap_or(1.0),
+                idle_limit: flag("--idle-limit")
+                    .flatten()
+                    .map(|value| parse_number(&value, "--idle-limit"))
+                    .transpose()?,
+            }
+        }
+        "config" => {
+            let subcommand = match positional.first() {
+                Some(&"check") | Some(&"migrate") => positional.remove(0),
+                _ => return Err(anyhow!("Missing subcommand")),
+            };
+            let path = (!positional.is_empty()).then(|| PathBuf::from(positional.remove(0)));
+            if subcommand == "migrate" {
+                Command::ConfigMigrate { path }
+            } else {
+                Command::ConfigCheck { path }
+            }
+        }
+        "hosts" => {
+            if positional.first() != Some(&"import-ssh") {
+                return Err(anyhow!("Missing subcommand"));
+            }
+            positional.remove(0);
+            Command::ImportSsh {
+                group: flag("--group").is_some(),
+                all: flag("--all").is_some(),
+                ssh_config: flag("--ssh-config").flatten(),
+            }
+        }
+        _ => unreachable!("command table and parser out of sync: {name}"),
+    };
+    if let Some((flag, _)) = flags.first() {
+        return Err(anyhow!("Unknown option for `{name}`: {flag}"));
+    }
+    if let Some(arg) = positional.first() {
+        return Err(anyhow!("Unexpected argument for `{name}`: {arg}"));
+    }
+    Ok(command)
+}
+
+fn pane_ref(positional: &mut Vec<&str>) -> Result<PaneRef> {
+    match positional.len() {
+        0 => Err(anyhow!("Missing pane")),
+        1 => Ok(PaneRef::Label(positional.remove(0).to_string())),
+        _ => {
+            let host = positional.remove(0).to_string();
+            let pane = positional.remove(0);
+            let pane_id = if pane.starts_with('%') {
+                pane.to_string()
+            } else {
+                format!("%{pane}")
+            };
+            Ok(PaneRef::Pane { host, pane_id })
+        }
+    }
+}
+
+fn parse_number<T: std::str::FromStr>(value: &str, flag: &str) -> Result<T>
+where
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    value
+        .parse::<T>()
+        .with_context(|| format!("Invalid {flag} value: {value}"))
+}
+
+fn suggest(name: &str) -> Option<&'static str> {
+    COMMANDS
+        .iter()
+        .map(|(command, _, _)| (*command, distance(name, command)))
+        .filter(|(command, distance)| *distance <= 2.max(command.len() / 3))
+        .min_by_key(|(_, distance)| *distance)
+        .map(|(command, _)| command)
+}
+
+fn distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut prev = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let next = (prev + usize::from(ca != *cb))
+                .min(row[j] + 1)
+                .min(row[j + 1] + 1);
+            prev = row[j + 1];
+            row[j + 1] = next;
+        }
+    }
+    row[b.len()]
+}
+
+pub fn help(topic: Option<&str>) -> String {
+    if let Some((name, usage, description)) = COMMANDS
+        .iter()
+        .find(|(command, _, _)| Some(*command) == topic)
+    {
+        let mut text = format!("{description}\n\nUsage: {usage}\n");
+        let extra = match *name {
+            "list" => "\n  --json  Print one JSON object per pane\n",
+            "capture" => {
+                "\n  <label>          A tracked pane or bookmark label\n  <host pane-id>   Any pane, e.g. `buildbox %3`\n  --lines <n>      Print the last <n> lines (default: ui.lines)\n"
+            }
+            "attach" => {
+                "\n  <label>          A tracked pane or bookmark label\n  <host pane-id>   Any pane, e.g. `buildbox %3`\n"
+            }
+            "watch" => "\n  --json  Print one JSON object per event\n",
+            _ => "",
+        };
+        text.push_str(extra);
+        text.push_str("\nGlobal options:\n");
+        text.push_str(OPTIONS);
+        return text;
+    }
+    let width = COMMANDS
+        .iter()
+        .map(|(name, _, _)| name.len())
+        .max()
+        .unwrap_or(0);
+    let mut text = String::from(
+        "fleetmux: watch tmux panes across many hosts\n\nUsage: fleetmux [options] [command]\n\nCommands:\n",
+    );
+    for (name, _, description) in COMMANDS {
+        text.push_str(&format!("  {name:width$}  {description}\n"));
+    }
+    text.push_str("\nOptions:\n");
+    text.push_str(OPTIONS);
+    text.push_str("\nRun `fleetmux <command> --help` for details on a command.\n");
+    text
+}
@@ -1,31 +1,57 @@
+use crate::{migrate, validate};
 use anyhow::{anyhow, Context, Result};
 use serde::{Deserialize, Serialize};
 use std::fs;
 use std::path::{Path, PathBuf};
-use std::time::Duration;
+use std::time::{Duration, SystemTime};
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
 #[serde(default)]
 pub struct Config {
+    pub version: u32,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub include: Vec<String>,
     pub ui: UiConfig,
     pub colors: ColorConfig,
     pub ssh: SshConfig,
     pub local: LocalConfig,
+    pub record: RecordConfig,
+    pub logs: LogConfig,
+    pub api: ApiConfig,
+    pub webhooks: Vec<WebhookConfig>,
+    pub notifications: NotificationsConfig,
+    pub hooks: Vec<HookConfig>,
     pub hosts: Vec<HostConfig>,
+    pub inventory: Vec<InventorySource>,
     pub tracked: Vec<TrackedPane>,
     pub bookmarks: Vec<TrackedPane>,
+    #[serde(skip)]
+    pub included: toml::Table,
+    #[serde(skip)]
+    pub warnings: Vec<String>,
 }
 
 impl Default for Config {
     fn default() -> Self {
         Self {
+            version: migrate::CURRENT_VERSION,
+            include: Vec::new(),
             ui: UiConfig::default(),
             colors: ColorConfig::default(),
             ssh: SshConfig::default(),
             local: LocalConfig::default(),
+            record: RecordConfig::default(),
+            logs: LogConfig::default(),
+            api: ApiConfig::default(),
+            webhooks: Vec::new(),
+            notifications: NotificationsConfig::default(),
+            hooks: Vec::new(),
             hosts: Vec::new(),
+            inventory: Vec::new(),
             tracked: Vec::new(),
             bookmarks: Vec::new(),
+            included: toml::Table::new(),
+            warnings: Vec::new(),
         }
     }
 }
@@ -35,8 +61,11 @@ impl Default for Config {
 pub struct UiConfig {
     pub refresh_ms: u64,
     pub lines: usize,
-    pub layout: String,
-    pub theme: String,
+    // Deprecated and ignored, see migrate::DEPRECATED. Never written back.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub layout: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub theme: Option<String>,
     pub compact: bool,
     pub ansi: bool,
     pub join_lines: bool,
@@ -47,7 +76,24 @@ pub struct UiConfig {
     pub macos_notify_only_when_inactive: bool,
     pub macos_notify_ignore_apps: Vec<String>,
     pub macos_notify_sender: Option<String>,
+    pub linux_notification_on_stop: bool,
+    pub linux_notify_urgency: String,
+    pub linux_notify_timeout_ms: i32,
+    pub linux_notify_actions: bool,
+    pub terminal_notification_on_stop: bool,
+    pub terminal_notify_format: String,
+    pub terminal_notify_passthrough: String,
     pub notify_snooze_sec: u64,
+    #[serde(skip)]
+    pub overrides: UiOverrides,
+}
+
+// Set from command line flags after loading, for a single run; never written
+// back to the file.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct UiOverrides {
+    pub refresh_ms: Option<u64>,
+    pub no_ansi: bool,
 }
 
 impl Default for UiConfig {
@@ -55,8 +101,8 @@ impl Default for UiConfig {
         Self {
             refresh_ms: 750,
             lines: 40,
-            layout: "auto".to_string(),
-            theme: "default".to_string(),
+            layout: None,
+            theme: None,
             compact: false,
             ansi: true,
             join_lines: false,
@@ -76,12 +122,28 @@ impl Default for UiConfig {
                 "Hyper".to_string(),
             ],
             macos_notify_sender: None,
+            linux_notification_on_stop: false,
+            linux_notify_urgency: "normal".to_string(),
+            linux_notify_timeout_ms: -1,
+            linux_notify_actions: true,
+            terminal_notification_on_stop: false,
+            terminal_notify_format: "osc9".to_string(),
+            terminal_notify_passthrough: "auto".to_string(),
             notify_snooze_sec: 20,
+            overrides: UiOverrides::default(),
         }
     }
 }
 
 impl UiConfig {
+    pub fn refresh(&self) -> Duration {
+        Duration::from_millis(self.overrides.refresh_ms.unwrap_or(self.refresh_ms))
+    }
+
+    pub fn ansi(&self) -> bool {
+        self.ansi && !self.overrides.no_ansi
+    }
+
     pub fn activity_windows(&self) -> (Duration, Duration) {
         let active = Duration::from_secs(self.activity_active_window_sec.max(1));
         let mut idle = Duration::from_secs(self.activity_idle_after_sec.max(1));
@@ -114,13 +176,68 @@ impl Default for ColorConfig {
     }
 }
 
-#[derive(Clone, Debug, Serialize, Deserialize)]
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 #[serde(default)]
 pub struct SshConfig {
     pub connect_timeout_sec: u64,
     pub control_master: bool,
     pub control_persist_sec: u64,
     pub path_extra: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub port: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub identity_file: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proxy_jump: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra_args: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HostSshConfig {
+    pub user: Option<String>,
+    pub port: Option<u16>,
+    pub identity_file: Option<String>,
+    pub proxy_jump: Option<String>,
+    pub connect_timeout_sec: Option<u64>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub path_extra: Vec<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub extra_args: Vec<String>,
+}
+
+impl SshConfig {
+    pub fn for_host(&self, host: &HostConfig) -> SshConfig {
+        let mut ssh = self.clone();
+        let Some(overrides) = &host.ssh else {
+            return ssh;
+        };
+        if overrides.user.is_some() {
+            ssh.user = overrides.user.clone();
+        }
+        if overrides.port.is_some() {
+            ssh.port = overrides.port;
+        }
+        if overrides.identity_file.is_some() {
+            ssh.identity_file = overrides.identity_file.clone();
+        }
+        if overrides.proxy_jump.is_some() {
+            ssh.proxy_jump = overrides.proxy_jump.clone();
+        }
+        if let Some(timeout) = overrides.connect_timeout_sec {
+            ssh.connect_timeout_sec = timeout;
+        }
+        for path in &overrides.path_extra {
+            if !ssh.path_extra.contains(path) {
+                ssh.path_extra.push(path.clone());
+            }
+        }
+        ssh.extra_args.extend(overrides.extra_args.iter().cloned());
+        ssh
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -151,20 +268,263 @@ impl Default for SshConfig {
                 "/usr/local/bin".to_string(),
                 "/opt/homebrew/bin".to_string(),
             ],
+            user: None,
+            port: None,
+            identity_file: None,
+            proxy_jump: None,
+            extra_args: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RecordConfig {
+    pub enabled: bool,
+    pub dir: Option<String>,
+}
+
+impl RecordConfig {
+    pub fn recordings_dir(&self) -> Result<PathBuf> {
+        if let Some(dir) = self.dir.as_deref().map(str::trim).filter(|dir| !dir.is_empty()) {
+            return Ok(expand_home(dir));
+        }
+        Ok(data_dir()?.join("recordings"))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct LogConfig {
+    pub enabled: bool,
+    pub dir: Option<String>,
+    pub timestamps: bool,
+    pub max_bytes: u64,
+    pub keep: usize,
+    pub max_age_days: u64,
+}
+
+impl Default for LogConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            dir: None,
+            timestamps: true,
+            max_bytes: 10 * 1024 * 1024,
+            keep: 5,
+            max_age_days: 0,
+        }
+    }
+}
+
+impl LogConfig {
+    pub fn logs_dir(&self) -> Result<PathBuf> {
+        if let Some(dir) = self.dir.as_deref().map(str::trim).filter(|dir| !dir.is_empty()) {
+            return Ok(expand_home(dir));
+        }
+        Ok(data_dir()?.join("logs"))
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ApiConfig {
+    pub enabled: bool,
+    pub bind: String,
+}
+
+impl Default for ApiConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            bind: "127.0.0.1:7766".to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WebhookConfig {
+    pub name: Option<String>,
+    pub url: String,
+    pub events: Vec<String>,
+    pub template: Option<String>,
+    pub headers: Vec<String>,
+    pub timeout_sec: u64,
+    pub retries: u32,
+    pub rate_limit_per_min: u32,
+}
+
+impl Default for WebhookConfig {
+    fn default() -> Self {
+        Self {
+            name: None,
+            url: String::new(),
+            events: Vec::new(),
+            template: None,
+            headers: Vec::new(),
+            timeout_sec: 5,
+            retries: 2,
+            rate_limit_per_min: 20,
+        }
+    }
+}
+
+impl WebhookConfig {
+    pub fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| self.url.clone())
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationsConfig {
+    pub command: Option<String>,
+    pub error_patterns: Vec<String>,
+    pub input_patterns: Vec<String>,
+    pub routes: Vec<NotificationRoute>,
+    pub quiet_hours: Vec<QuietHours>,
+    pub escalation: EscalationConfig,
+}
+
+impl Default for NotificationsConfig {
+    fn default() -> Self {
+        Self {
+            command: None,
+            error_patterns: Vec::new(),
+            input_patterns: vec![
+                r"(?i)[\[(]y(es)?/n(o)?[\])]\s*[:?]?\s*$".to_string(),
+                r"(?i)(password|passphrase)[^:]*:\s*$".to_string(),
+                r"(?i)continue\?\s*(\[.*\]|\(.*\))?\s*$".to_string(),
+                r"^(>>>|\.\.\.)\s*$".to_string(),
+            ],
+            routes: Vec::new(),
+            quiet_hours: Vec::new(),
+            escalation: EscalationConfig::default(),
         }
     }
 }
 
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct EscalationConfig {
+    pub events: Vec<String>,
+    pub renotify_after_min: u64,
+    pub escalate_after_min: u64,
+    pub backends: Vec<String>,
+}
+
+impl EscalationConfig {
+    pub fn renotify_after(&self) -> Option<Duration> {
+        (self.renotify_after_min > 0).then(|| Duration::from_secs(self.renotify_after_min * 60))
+    }
+
+    pub fn escalate_after(&self) -> Option<Duration> {
+        (self.escalate_after_min > 0).then(|| Duration::from_secs(self.escalate_after_min * 60))
+    }
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuietHours {
+    pub days: Vec<String>,
+    pub start: String,
+    pub end: String,
+    pub events: Vec<String>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct NotificationRoute {
+    pub events: Vec<String>,
+    pub backends: Vec<String>,
+    pub hosts: Vec<String>,
+    pub tags: Vec<String>,
+    pub panes: Vec<String>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct HookConfig {
+    pub name: Option<String>,
+    pub on: Vec<String>,
+    pub command: String,
+    pub timeout_sec: u64,
+    pub lines: usize,
+    pub hosts: Vec<String>,
+    pub tags: Vec<String>,
+    pub panes: Vec<String>,
+}
+
+impl Default for HookConfig {
+    fn default() -> Self {
+        Self {
+            name: None,
+            on: Vec::new(),
+            command: String::new(),
+            timeout_sec: 30,
+            lines: 20,
+            hosts: Vec::new(),
+            tags: Vec::new(),
+            panes: Vec::new(),
+        }
+    }
+}
+
+impl HookConfig {
+    pub fn display_name(&self) -> String {
+        self.name
+            .clone()
+            .filter(|name| !name.trim().is_empty())
+            .unwrap_or_else(|| self.command.clone())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct HostConfig {
     pub name: String,
+    #[serde(default)]
     pub targets: Vec<String>,
+    // Deprecated and ignored, see migrate::DEPRECATED.
+    #[serde(skip_serializing_if = "Option::is_none")]
     pub strategy: Option<String>,
     pub color: Option<String>,
     pub tags: Option<Vec<String>>,
+    pub ssh: Option<HostSshConfig>,
+    #[serde(skip)]
+    pub source: Option<String>,
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InventorySource {
+    pub name: String,
+    pub command: Option<String>,
+    pub file: Option<String>,
+    pub format: String,
+    pub refresh_sec: u64,
+    pub timeout_sec: u64,
+    pub tags: Vec<String>,
+}
+
+impl Default for InventorySource {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            command: None,
+            file: None,
+            format: "auto".to_string(),
+            refresh_sec: 300,
+            timeout_sec: 30,
+            tags: Vec::new(),
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
 pub struct TrackedPane {
     pub host: String,
     pub session: String,
@@ -173,30 +533,612 @@ pub struct TrackedPane {
     pub label: Option<String>,
 }
 
-pub fn config_path() -> Result<PathBuf> {
+impl TrackedPane {
+    pub fn same_pane(&self, other: &TrackedPane) -> bool {
+        self.host == other.host
+            && self.session == other.session
+            && self.window == other.window
+            && self.pane_id == other.pane_id
+    }
+
+    pub fn file_stem(&self) -> String {
+        let raw = format!(
+            "{}_{}_{}_{}",
+            self.host,
+            self.session,
+            self.window,
+            self.pane_id.trim_start_matches('%')
+        );
+        raw.chars()
+            .map(|ch| {
+                if ch.is_ascii_alphanumeric() || ch == '-' || ch == '_' || ch == '.' {
+                    ch
+                } else {
+                    '-'
+                }
+            })
+            .collect()
+    }
+}
+
+pub fn config_path(explicit: Option<&str>, profile: Option<&str>) -> Result<PathBuf> {
+    if let Some(profile) = profile {
+        if explicit.is_some() {
+            return Err(anyhow!("--config and --profile cannot be combined"));
+        }
+        if profile.is_empty() || profile.contains(['/', '\\']) || profile.starts_with('.') {
+            return Err(anyhow!("Invalid profile name: {profile:?}"));
+        }
+        return Ok(config_dir()?
+            .join("profiles")
+            .join(format!("{profile}.toml")));
+    }
+    if let Some(path) = explicit {
+        return Ok(expand_home(path));
+    }
+    if let Some(path) = std::env::var_os("FLEETMUX_CONFIG").filter(|path| !path.is_empty()) {
+        return Ok(expand_home(&path.to_string_lossy()));
+    }
+    Ok(config_dir()?.join("config.toml"))
+}
+
+pub fn config_dir() -> Result<PathBuf> {
+    let base = match std::env::var_os("XDG_CONFIG_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let home = std::env::var_os("HOME").ok_or_else(|| anyhow!("HOME not set"))?;
+            PathBuf::from(home).join(".config")
+        }
+    };
+    Ok(base.join("fleetmux"))
+}
+
+fn data_dir() -> Result<PathBuf> {
     let home = std::env::var_os("HOME").ok_or_else(|| anyhow!("HOME not set"))?;
     Ok(PathBuf::from(home)
-        .join(".config")
-        .join("fleetmux")
-        .join("config.toml"))
+        .join(".local")
+        .join("share")
+        .join("fleetmux"))
 }
 
-pub fn load(path: &Path) -> Result<Config> {
+pub fn expand_home(path: &str) -> PathBuf {
+    if let Some(rest) = path.strip_prefix("~/")
+        && let Some(home) = std::env::var_os("HOME")
+    {
+        return PathBuf::from(home).join(rest);
+    }
+    PathBuf::from(path)
+}
+
+pub fn apply_local_host(config: &mut Config) {
+    if !config.local.enabled {
+        return;
+    }
+    let name = config.local.name.trim();
+    let name = if name.is_empty() { "local" } else { name };
+    if config.hosts.iter().any(|host| host.name == name) {
+        return;
+    }
+    config.hosts.push(HostConfig {
+        name: name.to_string(),
+        targets: vec!["local".to_string()],
+        strategy: None,
+        color: config.local.color.clone(),
+        tags: Some(vec!["local".to_string()]),
+        ssh: None,
+        source: None,
+    });
+}
+
+pub type Problems = Vec<(PathBuf, Vec<validate::Diagnostic>)>;
+
+struct Layers {
+    included: toml::Table,
+    user: toml::Table,
+    problems: Problems,
+    warnings: Vec<String>,
+}
+
+// Validation looks at the files as written so reported lines match them;
+// older versions are only migrated in memory.
+fn read_layers(path: &Path) -> Result<Layers> {
     let contents = fs::read_to_string(path)
         .with_context(|| format!("Unable to read config file: {}", path.display()))?;
-    let config: Config = toml::from_str(&contents)
+    let migrated = migrate::migrate(&contents).with_context(|| path.display().to_string())?;
+    let mut layers = Layers {
+        included: toml::Table::new(),
+        user: toml::Table::new(),
+        problems: Vec::new(),
+        warnings: migrated
+            .warnings
+            .into_iter()
+            .map(|warning| format!("{}: {warning}", path.display()))
+            .collect(),
+    };
+    if let Some(from) = migrated.from {
+        layers.warnings.push(format!(
+            "{}: version {from} config; run `fleetmux config migrate` to upgrade it to version {} (saving from setup also upgrades it)",
+            path.display(),
+            migrate::CURRENT_VERSION
+        ));
+    }
+    let Ok(user) = toml::from_str::<toml::Table>(&migrated.contents) else {
+        layers
+            .problems
+            .push((path.to_path_buf(), validate::check(&contents, &[])));
+        return Ok(layers);
+    };
+    let patterns: Vec<&str> = user
+        .get("include")
+        .and_then(toml::Value::as_array)
+        .map(|patterns| patterns.iter().filter_map(toml::Value::as_str).collect())
+        .unwrap_or_default();
+    for file in resolve_includes(path, &patterns)? {
+        let contents = fs::read_to_string(&file)
+            .with_context(|| format!("Unable to read included config: {}", file.display()))?;
+        let migrated =
+            migrate::migrate(&contents).with_context(|| file.display().to_string())?;
+        layers.warnings.extend(
+            migrated
+                .warnings
+                .into_iter()
+                .map(|warning| format!("{}: {warning}", file.display())),
+        );
+        let diagnostics = validate::check(&contents, &[]);
+        if !diagnostics.is_empty() {
+            layers.problems.push((file, diagnostics));
+            continue;
+        }
+        let table: toml::Table = toml::from_str(&migrated.contents)
+            .with_context(|| format!("Unable to parse included config: {}", file.display()))?;
+        if table.contains_key("include") {
+            return Err(anyhow!(
+                "{}: include is only supported in the main config file",
+                file.display()
+            ));
+        }
+        merge_tables(&mut layers.included, table, true);
+    }
+    let included_hosts: Vec<String> = Config::from_table(&layers.included)
+        .map(|included| included.hosts.into_iter().map(|host| host.name).collect())
+        .unwrap_or_default();
+    let diagnostics = validate::check(&contents, &included_hosts);
+    if !diagnostics.is_empty() {
+        layers.problems.push((path.to_path_buf(), diagnostics));
+    }
+    layers.user = user;
+    Ok(layers)
+}
+
+fn resolve_includes(path: &Path, patterns: &[&str]) -> Result<Vec<PathBuf>> {
+    let base = path.parent().unwrap_or(Path::new("."));
+    let mut files = Vec::new();
+    for pattern in patterns {
+        let pattern = base.join(expand_home(pattern));
+        let name = pattern
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        if !name.contains(['*', '?']) {
+            if !pattern.exists() {
+                return Err(anyhow!("Included config not found: {}", pattern.display()));
+            }
+            files.push(pattern);
+            continue;
+        }
+        files.extend(expand_glob(&pattern));
+    }
+    Ok(files)
+}
+
+type Stamps = Vec<(PathBuf, Option<SystemTime>)>;
+
+pub struct ConfigWatcher {
+    path: PathBuf,
+    applied: Stamps,
+    pending: Option<Stamps>,
+}
+
+impl ConfigWatcher {
+    pub fn new(path: &Path) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            applied: stamps(path),
+            pending: None,
+        }
+    }
+
+    pub fn sync(&mut self) {
+        self.applied = stamps(&self.path);
+        self.pending = None;
+    }
+
+    // Editors often write in several steps, so only report a change once the
+    // files have stopped changing between two polls.
+    pub fn poll(&mut self) -> bool {
+        let current = stamps(&self.path);
+        if current == self.applied {
+            self.pending = None;
+            return false;
+        }
+        if self.pending.as_ref() != Some(&current) {
+            self.pending = Some(current);
+            return false;
+        }
+        self.applied = current;
+        self.pending = None;
+        true
+    }
+}
+
+fn stamps(path: &Path) -> Stamps {
+    let mut files = vec![path.to_path_buf()];
+    if let Ok(contents) = fs::read_to_string(path)
+        && let Ok(table) = toml::from_str::<toml::Table>(&contents)
+        && let Some(patterns) = table.get("include").and_then(toml::Value::as_array)
+    {
+        let patterns: Vec<&str> = patterns.iter().filter_map(toml::Value::as_str).collect();
+        files.extend(resolve_includes(path, &patterns).unwrap_or_default());
+    }
+    files
+        .into_iter()
+        .map(|file| {
+            let modified = fs::metadata(&file).and_then(|meta| meta.modified()).ok();
+            (file, modified)
+        })
+        .collect()
+}
+
+pub fn expand_glob(pattern: &Path) -> Vec<PathBuf> {
+    let name = pattern
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let dir = pattern.parent().unwrap_or(Path::new("."));
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut matched: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|entry| {
+            entry.is_file()
+                && entry
+                    .file_name()
+                    .is_some_and(|file| glob_match(&name, &file.to_string_lossy()))
+        })
+        .collect();
+    matched.sort();
+    matched
+}
+
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(ch) if *ch == '?' || *ch == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|ch| *ch == '*')
+}
+
+fn merge_tables(base: &mut toml::Table, overlay: toml::Table, root: bool) {
+    for (key, value) in overlay {
+        match (base.get_mut(&key), value) {
+            (Some(toml::Value::Array(hosts)), toml::Value::Array(overlay))
+                if root && key == "hosts" =>
+            {
+                merge_hosts(hosts, overlay);
+            }
+            (Some(toml::Value::Table(table)), toml::Value::Table(overlay)) => {
+                merge_tables(table, overlay, false);
+            }
+            (_, value) => {
+                base.insert(key, value);
+            }
+        }
+    }
+}
+
+fn merge_hosts(hosts: &mut Vec<toml::Value>, overlay: Vec<toml::Value>) {
+    for host in overlay {
+        let existing = hosts.iter_mut().find(|existing| {
+            existing.get("name").is_some() && existing.get("name") == host.get("name")
+        });
+        match (existing.and_then(toml::Value::as_table_mut), host) {
+            (Some(existing), toml::Value::Table(host)) => existing.extend(host),
+            (_, host) => hosts.push(host),
+        }
+    }
+}
+
+fn strip_included(table: &mut toml::Table, included: &toml::Table, root: bool) {
+    table.retain(|key, value| {
+        if root && key == "version" {
+            return true;
+        }
+        let Some(base) = included.get(key) else {
+            return true;
+        };
+        match (value, base) {
+            (toml::Value::Array(hosts), toml::Value::Array(base)) if root && key == "hosts" => {
+                strip_hosts(hosts, base);
+                !hosts.is_empty()
+            }
+            (toml::Value::Table(table), toml::Value::Table(base)) => {
+                strip_included(table, base, false);
+                !table.is_empty()
+            }
+            (value, base) => value != base,
+        }
+    });
+}
+
+fn strip_hosts(hosts: &mut Vec<toml::Value>, included: &[toml::Value]) {
+    hosts.retain_mut(|host| {
+        let Some(host) = host.as_table_mut() else {
+            return true;
+        };
+        let Some(base) = included
+            .iter()
+            .filter_map(toml::Value::as_table)
+            .find(|base| base.get("name").is_some() && base.get("name") == host.get("name"))
+        else {
+            return true;
+        };
+        host.retain(|key, value| key == "name" || base.get(key) != Some(value));
+        host.len() > 1
+    });
+}
+
+impl Config {
+    fn from_table(table: &toml::Table) -> Result<Self> {
+        Ok(toml::Value::Table(table.clone()).try_into()?)
+    }
+}
+
+pub fn check(path: &Path) -> Result<(Problems, Vec<String>)> {
+    let layers = read_layers(path)?;
+    Ok((layers.problems, layers.warnings))
+}
+
+pub fn load(path: &Path) -> Result<Config> {
+    let layers = read_layers(path)?;
+    if !layers.problems.is_empty() {
+        let lines: Vec<String> = layers
+            .problems
+            .iter()
+            .flat_map(|(file, problems)| {
+                problems
+                    .iter()
+                    .map(move |problem| format!("  {}:{problem}", file.display()))
+            })
+            .collect();
+        return Err(anyhow!("Invalid config:\n{}", lines.join("\n")));
+    }
+    let mut merged = layers.included.clone();
+    merge_tables(&mut merged, layers.user, true);
+    let mut config = Config::from_table(&merged)
         .with_context(|| format!("Unable to parse config file: {}", path.display()))?;
+    config.included = layers.included;
+    config.warnings = layers.warnings;
     Ok(config)
 }
 
 pub fn save(path: &Path, config: &Config) -> Result<()> {
+    let mut config = config.clone();
+    config.hosts.retain(|host| host.source.is_none());
     if let Some(parent) = path.parent() {
         fs::create_dir_all(parent).with_context(|| {
             format!("Unable to create config directory: {}", parent.display())
         })?;
     }
-    let contents = toml::to_string_pretty(config).context("Unable to serialize config")?;
+    let table = to_table(&config)?;
+    let contents = match fs::read_to_string(path) {
+        Ok(existing) => {
+            let migrated =
+                migrate::migrate(&existing).with_context(|| path.display().to_string())?;
+            if let Some(from) = migrated.from {
+                migrate::backup(path, &existing, from)?;
+            }
+            edit_document(path, &migrated.contents, &table)
+        }
+        Err(_) => None,
+    };
+    let contents = match contents {
+        Some(contents) => contents,
+        None => toml::to_string_pretty(&table).context("Unable to serialize config")?,
+    };
     fs::write(path, contents)
         .with_context(|| format!("Unable to write config file: {}", path.display()))?;
     Ok(())
 }
+
+fn to_table(config: &Config) -> Result<toml::Table> {
+    let mut table = toml::Table::try_from(config).context("Unable to serialize config")?;
+    if !config.included.is_empty() {
+        strip_included(&mut table, &config.included, true);
+    }
+    Ok(table)
+}
+
+// Writes only what changed since the file was last read, so comments,
+// ordering and formatting of untouched entries survive. Anything the
+// document can't represent falls back to a full rewrite.
+fn edit_document(path: &Path, existing: &str, desired: &toml::Table) -> Option<String> {
+    let mut document = existing.parse::<toml_edit::DocumentMut>().ok()?;
+    let mut previous = load(path).ok()?;
+    apply_local_host(&mut previous);
+    let previous = to_table(&previous).ok()?;
+    edit_table(document.as_table_mut(), &previous, desired);
+    Some(document.to_string())
+}
+
+fn edit_table(document: &mut dyn toml_edit::TableLike, old: &toml::Table, new: &toml::Table) {
+    for (key, value) in new {
+        let before = old.get(key);
+        if before == Some(value) {
+            continue;
+        }
+        let Some(item) = document.get_mut(key) else {
+            document.insert(key, to_item(value));
+            continue;
+        };
+        match (before, value) {
+            (Some(toml::Value::Table(before)), toml::Value::Table(value))
+                if item.is_table_like() =>
+            {
+                if let Some(table) = item.as_table_like_mut() {
+                    edit_table(table, before, value);
+                }
+            }
+            (before, toml::Value::Array(values)) if item.is_array_of_tables() => {
+                let before = before.and_then(toml::Value::as_array).map_or(&[][..], Vec::as_slice);
+                if let Some(tables) = item.as_array_of_tables_mut() {
+                    edit_tables(tables, before, values);
+                }
+            }
+            _ => {
+                let decor = item.as_value().map(|value| value.decor().clone());
+                let mut replacement = to_item(value);
+                if let (Some(decor), Some(value)) = (decor, replacement.as_value_mut()) {
+                    *value.decor_mut() = decor;
+                }
+                *item = replacement;
+            }
+        }
+    }
+    for key in old.keys() {
+        if !new.contains_key(key) {
+            document.remove(key);
+        }
+    }
+}
+
+fn edit_tables(
+    document: &mut toml_edit::ArrayOfTables,
+    old: &[toml::Value],
+    new: &[toml::Value],
+) {
+    let mut claimed = vec![false; old.len()];
+    let existing: Vec<(toml_edit::Table, toml::Table)> = document
+        .iter()
+        .map(|table| {
+            let parsed = toml::from_str::<toml::Table>(&table.to_string()).unwrap_or_default();
+            let filled = old.iter().enumerate().find_map(|(index, value)| {
+                let value = value.as_table()?;
+                let matches = !claimed[index]
+                    && parsed.iter().all(|(key, field)| value.get(key) == Some(field));
+                matches.then(|| {
+                    claimed[index] = true;
+                    value.clone()
+                })
+            });
+            (table.clone(), filled.unwrap_or(parsed))
+        })
+        .collect();
+    let new: Vec<&toml::Table> = new.iter().filter_map(toml::Value::as_table).collect();
+    let mut used = vec![false; existing.len()];
+    let mut picks: Vec<Option<usize>> = new
+        .iter()
+        .map(|value| {
+            let index = existing
+                .iter()
+                .enumerate()
+                .position(|(index, (_, filled))| !used[index] && filled == *value)?;
+            used[index] = true;
+            Some(index)
+        })
+        .collect();
+    for (pick, value) in picks.iter_mut().zip(&new) {
+        if pick.is_none() {
+            *pick = existing
+                .iter()
+                .enumerate()
+                .position(|(index, (_, filled))| !used[index] && same_entry(filled, value));
+            if let Some(index) = *pick {
+                used[index] = true;
+            }
+        }
+    }
+    let mut tables = toml_edit::ArrayOfTables::new();
+    for (pick, value) in picks.into_iter().zip(new) {
+        let table = match pick {
+            Some(index) => {
+                let (mut table, filled) = existing[index].clone();
+                edit_table(&mut table, &filled, value);
+                table
+            }
+            None => to_table_item(value),
+        };
+        tables.push(table);
+    }
+    *document = tables;
+}
+
+fn same_entry(a: &toml::Table, b: &toml::Table) -> bool {
+    let keys: &[&str] = if a.contains_key("name") {
+        &["name"]
+    } else {
+        &["host", "session", "window", "pane_id"]
+    };
+    keys.iter().all(|key| a.get(*key).is_some() && a.get(*key) == b.get(*key))
+}
+
+fn to_item(value: &toml::Value) -> toml_edit::Item {
+    match value {
+        toml::Value::Table(table) => toml_edit::Item::Table(to_table_item(table)),
+        toml::Value::Array(values)
+            if !values.is_empty() && values.iter().all(toml::Value::is_table) =>
+        {
+            let mut tables = toml_edit::ArrayOfTables::new();
+            for table in values.iter().filter_map(toml::Value::as_table) {
+                tables.push(to_table_item(table));
+            }
+            toml_edit::Item::ArrayOfTables(tables)
+        }
+        value => toml_edit::Item::Value(to_value(value)),
+    }
+}
+
+fn to_table_item(table: &toml::Table) -> toml_edit::Table {
+    let mut item = toml_edit::Table::new();
+    for (key, value) in table {
+        item.insert(key, to_item(value));
+    }
+    item
+}
+
+fn to_value(value: &toml::Value) -> toml_edit::Value {
+    match value {
+        toml::Value::String(value) => value.as_str().into(),
+        toml::Value::Integer(value) => (*value).into(),
+        toml::Value::Float(value) => (*value).into(),
+        toml::Value::Boolean(value) => (*value).into(),
+        toml::Value::Datetime(value) => (*value).into(),
+        toml::Value::Array(values) => values.iter().map(to_value).collect::<toml_edit::Array>().into(),
+        toml::Value::Table(table) => table
+            .iter()
+            .map(|(key, value)| (key.clone(), to_value(value)))
+            .collect::<toml_edit::InlineTable>()
+            .into(),
+    }
+}
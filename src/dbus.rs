@@ -0,0 +1,290 @@
+use crate::config::{TrackedPane, UiConfig};
+use anyhow::{anyhow, Result};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+const DEST: &str = "org.freedesktop.Notifications";
+const OBJECT_PATH: &str = "/org/freedesktop/Notifications";
+const TAKE_CONTROL: &str = "take-control";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Urgency {
+    Low,
+    Normal,
+    Critical,
+}
+
+impl Urgency {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "low" => Some(Urgency::Low),
+            "normal" => Some(Urgency::Normal),
+            "critical" => Some(Urgency::Critical),
+            _ => None,
+        }
+    }
+
+    fn byte(self) -> u8 {
+        match self {
+            Urgency::Low => 0,
+            Urgency::Normal => 1,
+            Urgency::Critical => 2,
+        }
+    }
+}
+
+struct Notification {
+    host: String,
+    pane: Option<TrackedPane>,
+    summary: String,
+    body: String,
+    urgency: Urgency,
+}
+
+pub struct DesktopNotifier {
+    tx: mpsc::UnboundedSender<Notification>,
+    action_rx: Option<mpsc::UnboundedReceiver<TrackedPane>>,
+    errors_rx: mpsc::UnboundedReceiver<String>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl DesktopNotifier {
+    pub fn new(config: &UiConfig) -> Self {
+        let actions: Arc<Mutex<HashMap<u32, TrackedPane>>> = Arc::new(Mutex::new(HashMap::new()));
+        let (tx, rx) = mpsc::unbounded_channel();
+        let (action_tx, action_rx) = mpsc::unbounded_channel();
+        let (errors_tx, errors_rx) = mpsc::unbounded_channel();
+        let mut tasks = vec![tokio::spawn(run_sender(
+            rx,
+            Arc::clone(&actions),
+            config.linux_notify_actions,
+            config.linux_notify_timeout_ms,
+            errors_tx,
+        ))];
+        if config.linux_notify_actions {
+            tasks.push(tokio::spawn(run_monitor(actions, action_tx)));
+        }
+        Self {
+            tx,
+            action_rx: Some(action_rx),
+            errors_rx,
+            tasks,
+        }
+    }
+
+    pub fn notify(
+        &self,
+        host: &str,
+        pane: Option<&TrackedPane>,
+        summary: &str,
+        body: &str,
+        urgency: Urgency,
+    ) {
+        let _ = self.tx.send(Notification {
+            host: host.to_string(),
+            pane: pane.cloned(),
+            summary: summary.to_string(),
+            body: body.to_string(),
+            urgency,
+        });
+    }
+
+    pub fn take_actions(&mut self) -> Option<mpsc::UnboundedReceiver<TrackedPane>> {
+        self.action_rx.take()
+    }
+
+    pub fn take_errors(&mut self) -> Vec<String> {
+        let mut errors = Vec::new();
+        while let Ok(error) = self.errors_rx.try_recv() {
+            errors.push(error);
+        }
+        errors
+    }
+}
+
+impl Drop for DesktopNotifier {
+    fn drop(&mut self) {
+        for task in &self.tasks {
+            task.abort();
+        }
+    }
+}
+
+pub async fn send_test(config: &UiConfig) -> Result<()> {
+    let urgency = Urgency::parse(&config.linux_notify_urgency).unwrap_or(Urgency::Normal);
+    call_notify(
+        0,
+        "FleetMux",
+        "Test notification from fleetmux",
+        urgency,
+        false,
+        config.linux_notify_timeout_ms,
+    )
+    .await
+    .map(|_| ())
+}
+
+async fn run_sender(
+    mut rx: mpsc::UnboundedReceiver<Notification>,
+    actions: Arc<Mutex<HashMap<u32, TrackedPane>>>,
+    with_actions: bool,
+    timeout_ms: i32,
+    errors_tx: mpsc::UnboundedSender<String>,
+) {
+    let mut replace_ids: HashMap<String, u32> = HashMap::new();
+    while let Some(notification) = rx.recv().await {
+        // Host alerts replace the previous alert for that host, pane alerts the one for that pane.
+        let key = match &notification.pane {
+            Some(pane) => format!("{}/{}", notification.host, pane.pane_id),
+            None => notification.host.clone(),
+        };
+        let replaces = replace_ids.get(&key).copied().unwrap_or(0);
+        let result = call_notify(
+            replaces,
+            &notification.summary,
+            &notification.body,
+            notification.urgency,
+            with_actions && notification.pane.is_some(),
+            timeout_ms,
+        )
+        .await;
+        match result {
+            Ok(id) => {
+                replace_ids.insert(key, id);
+                if let Some(pane) = notification.pane
+                    && let Ok(mut actions) = actions.lock()
+                {
+                    if replaces != 0 && replaces != id {
+                        actions.remove(&replaces);
+                    }
+                    actions.insert(id, pane);
+                }
+            }
+            Err(err) => {
+                let _ = errors_tx.send(format!("Desktop notification failed: {err}"));
+            }
+        }
+    }
+}
+
+async fn call_notify(
+    replaces: u32,
+    summary: &str,
+    body: &str,
+    urgency: Urgency,
+    with_actions: bool,
+    timeout_ms: i32,
+) -> Result<u32> {
+    let actions = if with_actions {
+        format!("['{TAKE_CONTROL}', 'Take control']")
+    } else {
+        "@as []".to_string()
+    };
+    let output = Command::new("gdbus")
+        .arg("call")
+        .arg("--session")
+        .arg("--timeout")
+        .arg("5")
+        .arg("--dest")
+        .arg(DEST)
+        .arg("--object-path")
+        .arg(OBJECT_PATH)
+        .arg("--method")
+        .arg(format!("{DEST}.Notify"))
+        .arg(gvariant_string("FleetMux"))
+        .arg(format!("uint32 {replaces}"))
+        .arg(gvariant_string(""))
+        .arg(gvariant_string(summary))
+        .arg(gvariant_string(body))
+        .arg(actions)
+        .arg(format!("{{'urgency': <byte {}>}}", urgency.byte()))
+        .arg(format!("int32 {timeout_ms}"))
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = tokio::time::timeout(Duration::from_secs(10), output)
+        .await
+        .map_err(|_| anyhow!("gdbus timed out"))?
+        .map_err(|err| anyhow!("Unable to run gdbus: {err}"))?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(anyhow!("{stderr}"));
+    }
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    parse_uint32(&stdout).ok_or_else(|| anyhow!("Unexpected gdbus reply: {}", stdout.trim()))
+}
+
+async fn run_monitor(
+    actions: Arc<Mutex<HashMap<u32, TrackedPane>>>,
+    action_tx: mpsc::UnboundedSender<TrackedPane>,
+) {
+    let child = Command::new("gdbus")
+        .arg("monitor")
+        .arg("--session")
+        .arg("--dest")
+        .arg(DEST)
+        .arg("--object-path")
+        .arg(OBJECT_PATH)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn();
+    let Ok(mut child) = child else {
+        return;
+    };
+    let Some(stdout) = child.stdout.take() else {
+        return;
+    };
+    let mut lines = BufReader::new(stdout).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if let Some(args) = signal_args(&line, "ActionInvoked") {
+            let Some(id) = parse_uint32(args) else {
+                continue;
+            };
+            if !args.contains(&format!("'{TAKE_CONTROL}'")) {
+                continue;
+            }
+            let pane = actions.lock().ok().and_then(|actions| actions.get(&id).cloned());
+            if let Some(pane) = pane {
+                let _ = action_tx.send(pane);
+            }
+        } else if let Some(args) = signal_args(&line, "NotificationClosed")
+            && let Some(id) = parse_uint32(args)
+            && let Ok(mut actions) = actions.lock()
+        {
+            actions.remove(&id);
+        }
+    }
+}
+
+fn signal_args<'a>(line: &'a str, signal: &str) -> Option<&'a str> {
+    let marker = format!("{DEST}.{signal} ");
+    line.split_once(&marker).map(|(_, args)| args)
+}
+
+fn parse_uint32(text: &str) -> Option<u32> {
+    let rest = &text[text.find("uint32 ")? + "uint32 ".len()..];
+    let digits: String = rest.chars().take_while(|ch| ch.is_ascii_digit()).collect();
+    digits.parse().ok()
+}
+
+fn gvariant_string(value: &str) -> String {
+    let mut quoted = String::from("'");
+    for ch in value.chars() {
+        match ch {
+            '\\' => quoted.push_str("\\\\"),
+            '\'' => quoted.push_str("\\'"),
+            '\n' => quoted.push_str("\\n"),
+            _ => quoted.push(ch),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
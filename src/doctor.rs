@@ -5,6 +5,9 @@ use anyhow::Result;
 
 pub async fn run(config: &Config) -> Result<()> {
     println!("FleetMux doctor");
+    for warning in &config.warnings {
+        println!("Warning: {warning}");
+    }
     println!("Hosts: {}", config.hosts.len());
 
     let mut resolver = HostResolver::new();
@@ -17,7 +20,8 @@ pub async fn run(config: &Config) -> Result<()> {
             println!("Color: {}", color);
         }
 
-        let target = match resolver.resolve_target(host, &config.ssh).await {
+        let ssh_cfg = config.ssh.for_host(host);
+        let target = match resolver.resolve_target(host, &ssh_cfg).await {
             Ok(target) => {
                 println!("Resolved target: {}", target);
                 target
@@ -28,7 +32,7 @@ pub async fn run(config: &Config) -> Result<()> {
             }
         };
 
-        match crate::ssh::run_ssh_command(&target, &config.ssh, "tmux -V").await {
+        match crate::ssh::run_ssh_command(&target, &ssh_cfg, "tmux -V").await {
             Ok(output) => println!("tmux: {}", output),
             Err(err) => {
                 println!("tmux error: {err:#}");
@@ -36,7 +40,7 @@ pub async fn run(config: &Config) -> Result<()> {
             }
         }
 
-        match tmux::list_windows(&target, &config.ssh).await {
+        match tmux::list_windows(&target, &ssh_cfg).await {
             Ok(windows) => {
                 println!("Windows: {}", windows.len());
                 for window in &windows {
@@ -51,7 +55,7 @@ pub async fn run(config: &Config) -> Result<()> {
             Err(err) => println!("Windows error: {err:#}"),
         }
 
-        let panes = match tmux::list_panes(&target, &config.ssh).await {
+        let panes = match tmux::list_panes(&target, &ssh_cfg).await {
             Ok(panes) => panes,
             Err(err) => {
                 println!("Panes error: {err:#}");
@@ -78,8 +82,8 @@ pub async fn run(config: &Config) -> Result<()> {
                 &pane.pane_id,
                 10,
                 config.ui.join_lines,
-                config.ui.ansi,
-                &config.ssh,
+                config.ui.ansi(),
+                &ssh_cfg,
             )
             .await {
                 Ok(capture) => {
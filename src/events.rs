@@ -0,0 +1,334 @@
+use crate::model::{ActivityTransitions, AppState, AttentionState, PaneState, PaneStatus};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::time::Instant;
+
+#[derive(Clone, Debug, Serialize)]
+pub struct PaneRef {
+    pub index: usize,
+    pub host: String,
+    pub session: String,
+    pub window: u32,
+    pub pane_id: String,
+    pub label: Option<String>,
+}
+
+impl PaneRef {
+    pub fn new(index: usize, pane: &PaneState) -> Self {
+        Self {
+            index,
+            host: pane.tracked.host.clone(),
+            session: pane.tracked.session.clone(),
+            window: pane.tracked.window,
+            pane_id: pane.tracked.pane_id.clone(),
+            label: pane.tracked.label.clone().filter(|label| !label.is_empty()),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let mut label = format!(
+            "{} {}:{} {}",
+            self.host, self.session, self.window, self.pane_id
+        );
+        if let Some(name) = &self.label {
+            label.push_str(&format!(" [{name}]"));
+        }
+        label
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ActivityChange {
+    Active,
+    Stopped,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HostStatus {
+    Up,
+    Down,
+}
+
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum FleetEvent {
+    PaneStatus {
+        ts: String,
+        #[serde(flatten)]
+        pane: PaneRef,
+        status: PaneStatus,
+        previous: PaneStatus,
+        error: Option<String>,
+    },
+    Activity {
+        ts: String,
+        #[serde(flatten)]
+        pane: PaneRef,
+        activity: ActivityChange,
+    },
+    Attention {
+        ts: String,
+        #[serde(flatten)]
+        pane: PaneRef,
+        attention: AttentionState,
+        previous: AttentionState,
+    },
+    CommandFinished {
+        ts: String,
+        #[serde(flatten)]
+        pane: PaneRef,
+        command: String,
+        exit_status: Option<i32>,
+        duration_sec: u64,
+    },
+    Host {
+        ts: String,
+        host: String,
+        status: HostStatus,
+        target: Option<String>,
+        previous_target: Option<String>,
+    },
+}
+
+impl FleetEvent {
+    pub fn describe(&self) -> String {
+        match self {
+            FleetEvent::PaneStatus {
+                pane,
+                status,
+                previous,
+                error,
+                ..
+            } => {
+                let mut line = format!(
+                    "{} status {} -> {}",
+                    pane.describe(),
+                    status_name(previous),
+                    status_name(status)
+                );
+                if let Some(error) = error {
+                    line.push_str(&format!(" ({error})"));
+                }
+                line
+            }
+            FleetEvent::Activity { pane, activity, .. } => {
+                let name = match activity {
+                    ActivityChange::Active => "active",
+                    ActivityChange::Stopped => "stopped",
+                };
+                format!("{} {name}", pane.describe())
+            }
+            FleetEvent::Attention {
+                pane,
+                attention,
+                previous,
+                ..
+            } => format!(
+                "{} attention {} -> {}",
+                pane.describe(),
+                attention_name(*previous),
+                attention_name(*attention)
+            ),
+            FleetEvent::CommandFinished {
+                pane,
+                command,
+                exit_status,
+                ..
+            } => match exit_status {
+                Some(code) => format!("{} {command} finished, exit {code}", pane.describe()),
+                None => format!("{} {command} finished", pane.describe()),
+            },
+            FleetEvent::Host {
+                host,
+                status,
+                target,
+                ..
+            } => match (status, target) {
+                (HostStatus::Up, Some(target)) => format!("{host} up via {target}"),
+                (HostStatus::Up, None) => format!("{host} up"),
+                (HostStatus::Down, _) => format!("{host} down"),
+            },
+        }
+    }
+
+    pub fn ts(&self) -> &str {
+        match self {
+            FleetEvent::PaneStatus { ts, .. }
+            | FleetEvent::Activity { ts, .. }
+            | FleetEvent::Attention { ts, .. }
+            | FleetEvent::CommandFinished { ts, .. }
+            | FleetEvent::Host { ts, .. } => ts,
+        }
+    }
+}
+
+pub struct EventTracker {
+    panes: Vec<(String, String, u32, String)>,
+    statuses: Vec<PaneStatus>,
+    attention: Vec<AttentionState>,
+    finished: Vec<Option<Instant>>,
+    host_targets: HashMap<String, Option<String>>,
+}
+
+impl EventTracker {
+    pub fn new(state: &AppState) -> Self {
+        Self {
+            panes: pane_keys(state),
+            statuses: state.panes.iter().map(|pane| pane.status.clone()).collect(),
+            attention: state.attention.clone(),
+            finished: finished_at(state),
+            host_targets: state.host_targets.clone(),
+        }
+    }
+
+    pub fn collect(
+        &mut self,
+        state: &AppState,
+        transitions: Option<&ActivityTransitions>,
+    ) -> Vec<FleetEvent> {
+        if self.panes != pane_keys(state) || self.attention.len() != state.panes.len() {
+            *self = Self::new(state);
+            return Vec::new();
+        }
+        let ts = timestamp();
+        let mut events = Vec::new();
+
+        let mut hosts: Vec<&String> = state.host_targets.keys().collect();
+        hosts.sort();
+        for host in hosts {
+            let target = state.host_targets.get(host).cloned().flatten();
+            let previous = self.host_targets.get(host).cloned();
+            let changed = match &previous {
+                Some(previous) => previous != &target,
+                None => true,
+            };
+            if changed {
+                events.push(FleetEvent::Host {
+                    ts: ts.clone(),
+                    host: host.clone(),
+                    status: if target.is_some() {
+                        HostStatus::Up
+                    } else {
+                        HostStatus::Down
+                    },
+                    target: target.clone(),
+                    previous_target: previous.flatten(),
+                });
+            }
+        }
+        self.host_targets = state.host_targets.clone();
+
+        for (index, pane) in state.panes.iter().enumerate() {
+            let previous = self.statuses[index].clone();
+            if previous != pane.status {
+                events.push(FleetEvent::PaneStatus {
+                    ts: ts.clone(),
+                    pane: PaneRef::new(index, pane),
+                    status: pane.status.clone(),
+                    previous,
+                    error: pane.error.clone(),
+                });
+                self.statuses[index] = pane.status.clone();
+            }
+        }
+
+        for (index, pane) in state.panes.iter().enumerate() {
+            let Some(finished) = &pane.finished else {
+                continue;
+            };
+            if self.finished[index] != Some(finished.at) {
+                events.push(FleetEvent::CommandFinished {
+                    ts: ts.clone(),
+                    pane: PaneRef::new(index, pane),
+                    command: finished.command.clone(),
+                    exit_status: finished.exit_status,
+                    duration_sec: finished.duration_sec,
+                });
+                self.finished[index] = Some(finished.at);
+            }
+        }
+
+        if let Some(transitions) = transitions {
+            for (indices, activity) in [
+                (&transitions.active, ActivityChange::Active),
+                (&transitions.stopped, ActivityChange::Stopped),
+            ] {
+                for &index in indices {
+                    if let Some(pane) = state.panes.get(index) {
+                        events.push(FleetEvent::Activity {
+                            ts: ts.clone(),
+                            pane: PaneRef::new(index, pane),
+                            activity,
+                        });
+                    }
+                }
+            }
+        }
+
+        for (index, pane) in state.panes.iter().enumerate() {
+            let current = state
+                .attention
+                .get(index)
+                .copied()
+                .unwrap_or(AttentionState::None);
+            let previous = self.attention[index];
+            if previous != current {
+                events.push(FleetEvent::Attention {
+                    ts: ts.clone(),
+                    pane: PaneRef::new(index, pane),
+                    attention: current,
+                    previous,
+                });
+                self.attention[index] = current;
+            }
+        }
+
+        events
+    }
+}
+
+fn pane_keys(state: &AppState) -> Vec<(String, String, u32, String)> {
+    state
+        .panes
+        .iter()
+        .map(|pane| {
+            (
+                pane.tracked.host.clone(),
+                pane.tracked.session.clone(),
+                pane.tracked.window,
+                pane.tracked.pane_id.clone(),
+            )
+        })
+        .collect()
+}
+
+fn finished_at(state: &AppState) -> Vec<Option<Instant>> {
+    state
+        .panes
+        .iter()
+        .map(|pane| pane.finished.as_ref().map(|finished| finished.at))
+        .collect()
+}
+
+fn timestamp() -> String {
+    chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false)
+}
+
+fn status_name(status: &PaneStatus) -> &'static str {
+    match status {
+        PaneStatus::Ok => "ok",
+        PaneStatus::Down => "down",
+        PaneStatus::Stale => "stale",
+    }
+}
+
+fn attention_name(attention: AttentionState) -> &'static str {
+    match attention {
+        AttentionState::None => "none",
+        AttentionState::Manual => "manual",
+        AttentionState::Done => "done",
+        AttentionState::Input => "input",
+    }
+}
@@ -0,0 +1,267 @@
+use crate::config::{HookConfig, TrackedPane};
+use crate::events::{ActivityChange, FleetEvent};
+use crate::model::{self, AppState};
+use crate::notify::{Filter, Notification, NotifyKind};
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::{mpsc, Semaphore};
+
+const MAX_RUNNING: usize = 8;
+const ACTIVE: &str = "active";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum HookEvent {
+    Notify(NotifyKind),
+    Active,
+}
+
+impl HookEvent {
+    fn name(self) -> &'static str {
+        match self {
+            HookEvent::Notify(kind) => kind.name(),
+            HookEvent::Active => ACTIVE,
+        }
+    }
+
+    fn parse(name: &str) -> Result<Self> {
+        if name.trim() == ACTIVE {
+            return Ok(HookEvent::Active);
+        }
+        NotifyKind::parse(name)
+            .map(HookEvent::Notify)
+            .map_err(|err| anyhow!("{err}, or {ACTIVE}"))
+    }
+}
+
+struct Fired {
+    event: HookEvent,
+    host: String,
+    pane: Option<TrackedPane>,
+    env: Vec<(String, String)>,
+}
+
+struct Hook {
+    name: String,
+    command: String,
+    events: Vec<HookEvent>,
+    filter: Filter,
+    timeout: Duration,
+    lines: usize,
+}
+
+pub struct HookRun {
+    pub name: String,
+    pub command: String,
+    pub env: Vec<(String, String)>,
+    pub stdin: String,
+    pub timeout: Duration,
+}
+
+pub struct Hooks {
+    hooks: Vec<Hook>,
+    running: Arc<Semaphore>,
+    errors_tx: mpsc::UnboundedSender<String>,
+    errors_rx: mpsc::UnboundedReceiver<String>,
+}
+
+impl Hooks {
+    pub fn new(configs: &[HookConfig]) -> Result<Self> {
+        let mut hooks = Vec::new();
+        for config in configs {
+            let name = config.display_name();
+            if config.command.trim().is_empty() {
+                return Err(anyhow!("Invalid hook {name}: command is required"));
+            }
+            if config.on.is_empty() {
+                return Err(anyhow!("Invalid hook {name}: on is required"));
+            }
+            let events = config
+                .on
+                .iter()
+                .map(|event| HookEvent::parse(event))
+                .collect::<Result<Vec<_>>>()
+                .with_context(|| format!("Invalid hook {name}"))?;
+            hooks.push(Hook {
+                name,
+                command: config.command.clone(),
+                events,
+                filter: Filter {
+                    hosts: config.hosts.clone(),
+                    tags: config.tags.clone(),
+                    panes: config.panes.clone(),
+                },
+                timeout: Duration::from_secs(config.timeout_sec.max(1)),
+                lines: config.lines,
+            });
+        }
+        let (errors_tx, errors_rx) = mpsc::unbounded_channel();
+        Ok(Self {
+            hooks,
+            running: Arc::new(Semaphore::new(MAX_RUNNING)),
+            errors_tx,
+            errors_rx,
+        })
+    }
+
+    pub fn run(
+        &mut self,
+        state: &AppState,
+        events: &[FleetEvent],
+        notifications: &[Notification],
+        host_tags: &HashMap<String, Vec<String>>,
+    ) {
+        if self.hooks.is_empty() {
+            return;
+        }
+        let mut fired = Vec::new();
+        for notification in notifications {
+            fired.push(Fired {
+                event: HookEvent::Notify(notification.kind),
+                host: notification.host.clone(),
+                pane: notification.pane.clone(),
+                env: vec![
+                    ("FLEETMUX_MESSAGE".to_string(), notification.text()),
+                    (
+                        "FLEETMUX_TARGET".to_string(),
+                        notification.target.clone().unwrap_or_default(),
+                    ),
+                    ("FLEETMUX_TS".to_string(), notification.ts.clone()),
+                ],
+            });
+        }
+        for event in events {
+            if let FleetEvent::Activity {
+                ts,
+                pane,
+                activity: ActivityChange::Active,
+            } = event
+                && let Some(pane_state) = state.panes.get(pane.index)
+            {
+                fired.push(Fired {
+                    event: HookEvent::Active,
+                    host: pane.host.clone(),
+                    pane: Some(pane_state.tracked.clone()),
+                    env: vec![
+                        ("FLEETMUX_MESSAGE".to_string(), format!("Pane active: {}", pane.describe())),
+                        ("FLEETMUX_TS".to_string(), ts.clone()),
+                    ],
+                });
+            }
+        }
+
+        for fired in fired {
+            let pane = fired.pane.as_ref();
+            for hook in &self.hooks {
+                if !hook.events.contains(&fired.event)
+                    || !hook.filter.matches(&fired.host, pane, host_tags)
+                {
+                    continue;
+                }
+                let mut env = pane_env(fired.event.name(), &fired.host, pane);
+                env.extend(fired.env.iter().cloned());
+                let stdin = pane
+                    .and_then(|pane| state.pane_index(pane))
+                    .and_then(|index| state.panes[index].last_capture.as_ref())
+                    .map(|capture| last_lines(&capture.lines, hook.lines))
+                    .unwrap_or_default();
+                spawn(
+                    HookRun {
+                        name: hook.name.clone(),
+                        command: hook.command.clone(),
+                        env,
+                        stdin,
+                        timeout: hook.timeout,
+                    },
+                    &self.running,
+                    &self.errors_tx,
+                );
+            }
+        }
+    }
+
+    pub fn take_errors(&mut self) -> Vec<String> {
+        let mut errors = Vec::new();
+        while let Ok(error) = self.errors_rx.try_recv() {
+            errors.push(error);
+        }
+        errors
+    }
+}
+
+pub fn pane_env(event: &str, host: &str, pane: Option<&TrackedPane>) -> Vec<(String, String)> {
+    let mut env = vec![
+        ("FLEETMUX_EVENT".to_string(), event.to_string()),
+        ("FLEETMUX_HOST".to_string(), host.to_string()),
+    ];
+    if let Some(pane) = pane {
+        env.push(("FLEETMUX_SESSION".to_string(), pane.session.clone()));
+        env.push(("FLEETMUX_WINDOW".to_string(), pane.window.to_string()));
+        env.push(("FLEETMUX_PANE_ID".to_string(), pane.pane_id.clone()));
+        env.push((
+            "FLEETMUX_LABEL".to_string(),
+            pane.label.clone().unwrap_or_default(),
+        ));
+    }
+    env
+}
+
+pub fn spawn(run: HookRun, running: &Arc<Semaphore>, errors_tx: &mpsc::UnboundedSender<String>) {
+    let Ok(permit) = Arc::clone(running).try_acquire_owned() else {
+        let _ = errors_tx.send(format!("Hook {} skipped: too many hooks running", run.name));
+        return;
+    };
+    let errors_tx = errors_tx.clone();
+    tokio::spawn(async move {
+        let _permit = permit;
+        if let Err(err) = execute(&run).await {
+            let _ = errors_tx.send(format!("Hook {} failed: {err}", run.name));
+        }
+    });
+}
+
+async fn execute(run: &HookRun) -> Result<()> {
+    let mut child = Command::new("sh")
+        .arg("-c")
+        .arg(&run.command)
+        .envs(run.env.iter().map(|(key, value)| (key, value)))
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Unable to run sh")?;
+    let stdin = child.stdin.take();
+    let finished = tokio::time::timeout(run.timeout, async {
+        if let Some(mut stdin) = stdin {
+            let _ = stdin.write_all(run.stdin.as_bytes()).await;
+        }
+        child.wait().await
+    })
+    .await;
+    let status = match finished {
+        Ok(status) => status?,
+        Err(_) => {
+            let _ = child.kill().await;
+            return Err(anyhow!("timed out after {}s", run.timeout.as_secs()));
+        }
+    };
+    if !status.success() {
+        return Err(anyhow!("exited with {status}"));
+    }
+    Ok(())
+}
+
+fn last_lines(lines: &[String], count: usize) -> String {
+    let lines = model::plain_lines(lines);
+    let start = lines.len().saturating_sub(count);
+    let mut text = lines[start..].join("\n");
+    if !text.is_empty() {
+        text.push('\n');
+    }
+    text
+}
@@ -0,0 +1,296 @@
+use crate::config::{self, Config, HostConfig, InventorySource};
+use anyhow::{anyhow, Context, Result};
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+pub struct InventoryUpdate {
+    pub source: String,
+    pub result: Result<Vec<HostConfig>>,
+}
+
+pub struct InventoryHandle {
+    tx: mpsc::Sender<InventoryUpdate>,
+    tasks: Vec<JoinHandle<()>>,
+}
+
+impl InventoryHandle {
+    pub fn restart(&mut self, config: &Config) {
+        self.stop();
+        self.tasks = spawn_sources(config, &self.tx, true);
+    }
+
+    pub fn stop(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+    }
+}
+
+impl Drop for InventoryHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+pub fn start(config: &Config, tx: mpsc::Sender<InventoryUpdate>) -> InventoryHandle {
+    let tasks = spawn_sources(config, &tx, false);
+    InventoryHandle { tx, tasks }
+}
+
+fn spawn_sources(
+    config: &Config,
+    tx: &mpsc::Sender<InventoryUpdate>,
+    immediate: bool,
+) -> Vec<JoinHandle<()>> {
+    config
+        .inventory
+        .iter()
+        .cloned()
+        .map(|source| {
+            let tx = tx.clone();
+            tokio::spawn(async move {
+                let refresh = Duration::from_secs(source.refresh_sec.max(10));
+                if !immediate {
+                    tokio::time::sleep(refresh).await;
+                }
+                loop {
+                    let result = fetch(&source).await;
+                    let update = InventoryUpdate {
+                        source: source.name.clone(),
+                        result,
+                    };
+                    if tx.send(update).await.is_err() {
+                        return;
+                    }
+                    tokio::time::sleep(refresh).await;
+                }
+            })
+        })
+        .collect()
+}
+
+pub async fn load_all(config: &mut Config) -> Vec<String> {
+    let mut errors = Vec::new();
+    for source in config.inventory.clone() {
+        match fetch(&source).await {
+            Ok(hosts) => {
+                apply(config, &source.name, hosts);
+            }
+            Err(err) => errors.push(format!("Inventory {}: {err:#}", source.name)),
+        }
+    }
+    errors
+}
+
+pub fn apply(config: &mut Config, source: &str, hosts: Vec<HostConfig>) -> bool {
+    let before: Vec<HostConfig> = config
+        .hosts
+        .iter()
+        .filter(|host| host.source.as_deref() == Some(source))
+        .cloned()
+        .collect();
+    config
+        .hosts
+        .retain(|host| host.source.as_deref() != Some(source));
+    let tags = config
+        .inventory
+        .iter()
+        .find(|inventory| inventory.name == source)
+        .map(|inventory| inventory.tags.clone())
+        .unwrap_or_default();
+    let mut after = Vec::new();
+    for mut host in hosts {
+        if config.hosts.iter().any(|existing| existing.name == host.name)
+            || after.iter().any(|existing: &HostConfig| existing.name == host.name)
+        {
+            continue;
+        }
+        if host.targets.is_empty() {
+            host.targets = vec![host.name.clone()];
+        }
+        if !tags.is_empty() {
+            let host_tags = host.tags.get_or_insert_with(Vec::new);
+            for tag in &tags {
+                if !host_tags.contains(tag) {
+                    host_tags.push(tag.clone());
+                }
+            }
+        }
+        host.source = Some(source.to_string());
+        after.push(host);
+    }
+    let changed = before != after;
+    config.hosts.extend(after);
+    changed
+}
+
+pub fn carry_over(from: &Config, to: &mut Config) {
+    let mut by_source: HashMap<String, Vec<HostConfig>> = HashMap::new();
+    for host in &from.hosts {
+        if let Some(source) = &host.source {
+            by_source
+                .entry(source.clone())
+                .or_default()
+                .push(host.clone());
+        }
+    }
+    to.hosts.retain(|host| host.source.is_none());
+    for source in to.inventory.clone() {
+        if let Some(hosts) = by_source.remove(&source.name) {
+            apply(to, &source.name, hosts);
+        }
+    }
+}
+
+pub async fn fetch(source: &InventorySource) -> Result<Vec<HostConfig>> {
+    let timeout = Duration::from_secs(source.timeout_sec.max(1));
+    let (contents, path_hint) = match (&source.command, &source.file) {
+        (Some(command), _) => (run_command(command, timeout).await?, None),
+        (None, Some(file)) => {
+            let path = config::expand_home(file);
+            let contents = tokio::fs::read_to_string(&path)
+                .await
+                .with_context(|| format!("Unable to read {}", path.display()))?;
+            (contents, Some(path))
+        }
+        (None, None) => return Err(anyhow!("needs a command or a file")),
+    };
+    let format = match source.format.as_str() {
+        "auto" if path_hint
+            .as_ref()
+            .is_some_and(|path| path.extension().is_some_and(|ext| ext == "toml")) =>
+        {
+            "toml"
+        }
+        "auto" => "json",
+        other => other,
+    };
+    parse(format, &contents)
+}
+
+async fn run_command(command: &str, timeout: Duration) -> Result<String> {
+    let child = Command::new("sh")
+        .arg("-c")
+        .arg(command)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .context("Unable to run sh")?;
+    let output = tokio::time::timeout(timeout, child.wait_with_output())
+        .await
+        .map_err(|_| anyhow!("timed out after {}s", timeout.as_secs()))??;
+    if !output.status.success() {
+        return Err(anyhow!(
+            "command failed ({}): {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).to_string())
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum JsonHosts {
+    List(Vec<HostConfig>),
+    Table { hosts: Vec<HostConfig> },
+}
+
+#[derive(Deserialize)]
+struct TomlHosts {
+    #[serde(default)]
+    hosts: Vec<HostConfig>,
+}
+
+pub fn parse(format: &str, contents: &str) -> Result<Vec<HostConfig>> {
+    match format {
+        "json" => match serde_json::from_str(contents).context("Invalid JSON inventory")? {
+            JsonHosts::List(hosts) | JsonHosts::Table { hosts } => Ok(hosts),
+        },
+        "toml" => Ok(toml::from_str::<TomlHosts>(contents)
+            .context("Invalid TOML inventory")?
+            .hosts),
+        "tailscale" => parse_tailscale(contents),
+        other => Err(anyhow!(
+            "Unknown inventory format {other:?} (expected auto, json, toml or tailscale)"
+        )),
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TailscaleStatus {
+    #[serde(default)]
+    peer: HashMap<String, TailscalePeer>,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct TailscalePeer {
+    host_name: String,
+    #[serde(rename = "DNSName", default)]
+    dns_name: String,
+    #[serde(rename = "TailscaleIPs", default)]
+    tailscale_ips: Vec<String>,
+    #[serde(default)]
+    online: bool,
+    #[serde(rename = "OS", default)]
+    os: String,
+    #[serde(default)]
+    tags: Option<Vec<String>>,
+}
+
+fn parse_tailscale(contents: &str) -> Result<Vec<HostConfig>> {
+    let status: TailscaleStatus =
+        serde_json::from_str(contents).context("Invalid tailscale status JSON")?;
+    let mut hosts: Vec<HostConfig> = status
+        .peer
+        .into_values()
+        .filter(|peer| peer.online)
+        .map(|peer| {
+            let mut targets = Vec::new();
+            let dns_name = peer.dns_name.trim_end_matches('.');
+            if !dns_name.is_empty() {
+                targets.push(dns_name.to_string());
+            }
+            targets.extend(
+                peer.tailscale_ips
+                    .into_iter()
+                    .filter(|ip| ip.starts_with("100.")),
+            );
+            let mut tags = vec!["tailscale".to_string()];
+            if !peer.os.is_empty() {
+                tags.push(peer.os.to_lowercase());
+            }
+            tags.extend(
+                peer.tags
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(|tag| tag.trim_start_matches("tag:").to_string()),
+            );
+            let name = dns_name
+                .split('.')
+                .next()
+                .filter(|name| !name.is_empty())
+                .map_or(peer.host_name.clone(), str::to_string);
+            HostConfig {
+                name,
+                targets,
+                strategy: None,
+                color: None,
+                tags: Some(tags),
+                ssh: None,
+                source: None,
+            }
+        })
+        .collect();
+    hosts.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(hosts)
+}
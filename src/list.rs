@@ -0,0 +1,160 @@
+use crate::config::{Config, HostConfig, SshConfig, TrackedPane};
+use crate::ssh::HostResolver;
+use crate::tmux;
+use anyhow::Result;
+use serde::Serialize;
+use std::collections::HashMap;
+
+#[derive(Serialize)]
+struct PaneRow {
+    host: String,
+    session: String,
+    window: u32,
+    window_name: String,
+    pane_id: String,
+    command: String,
+    title: String,
+    tracked: bool,
+    bookmarked: bool,
+    label: Option<String>,
+}
+
+impl PaneRow {
+    fn mark(&self) -> &'static str {
+        match (self.tracked, self.bookmarked) {
+            (true, true) => "tracked,bookmark",
+            (true, false) => "tracked",
+            (false, true) => "bookmark",
+            (false, false) => "-",
+        }
+    }
+}
+
+pub async fn run(config: &Config, json: bool) -> Result<()> {
+    let tasks: Vec<_> = config
+        .hosts
+        .iter()
+        .map(|host| {
+            let host = host.clone();
+            let ssh_cfg = config.ssh.for_host(&host);
+            tokio::spawn(async move { load_host(&host, &ssh_cfg).await })
+        })
+        .collect();
+
+    let mut rows = Vec::new();
+    let mut failed = 0;
+    for (host, task) in config.hosts.iter().zip(tasks) {
+        let result = match task.await {
+            Ok(result) => result,
+            Err(err) => Err(err.into()),
+        };
+        match result {
+            Ok(panes) => rows.extend(panes.into_iter().map(|row| mark(config, row))),
+            Err(err) => {
+                failed += 1;
+                eprintln!("{}: {err:#}", host.name);
+            }
+        }
+    }
+
+    if json {
+        for row in &rows {
+            println!("{}", serde_json::to_string(row)?);
+        }
+    } else {
+        print_table(&rows);
+    }
+    if failed > 0 {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+async fn load_host(host: &HostConfig, ssh_cfg: &SshConfig) -> Result<Vec<PaneRow>> {
+    let target = HostResolver::new().resolve_target(host, ssh_cfg).await?;
+    let (windows, panes) = tokio::join!(
+        tmux::list_windows(&target, ssh_cfg),
+        tmux::list_panes(&target, ssh_cfg)
+    );
+    let window_names: HashMap<(String, u32), String> = windows
+        .unwrap_or_default()
+        .into_iter()
+        .map(|window| ((window.session, window.window), window.name))
+        .collect();
+    Ok(panes?
+        .into_iter()
+        .map(|pane| PaneRow {
+            host: host.name.clone(),
+            window_name: window_names
+                .get(&(pane.session.clone(), pane.window))
+                .cloned()
+                .unwrap_or_default(),
+            session: pane.session,
+            window: pane.window,
+            pane_id: pane.pane_id,
+            command: pane.command,
+            title: pane.title,
+            tracked: false,
+            bookmarked: false,
+            label: None,
+        })
+        .collect())
+}
+
+fn mark(config: &Config, mut row: PaneRow) -> PaneRow {
+    let matches = |pane: &&TrackedPane| pane.host == row.host && pane.pane_id == row.pane_id;
+    let tracked = config.tracked.iter().find(matches);
+    let bookmark = config.bookmarks.iter().find(matches);
+    row.tracked = tracked.is_some();
+    row.bookmarked = bookmark.is_some();
+    row.label = tracked
+        .and_then(|pane| pane.label.clone())
+        .or_else(|| bookmark.and_then(|pane| pane.label.clone()));
+    row
+}
+
+fn print_table(rows: &[PaneRow]) {
+    let header = ["HOST", "SESSION", "WINDOW", "NAME", "PANE", "COMMAND", "MARK", "TITLE"];
+    let cells: Vec<[String; 8]> = rows
+        .iter()
+        .map(|row| {
+            [
+                row.host.clone(),
+                row.session.clone(),
+                row.window.to_string(),
+                if row.window_name.is_empty() {
+                    "-".to_string()
+                } else {
+                    row.window_name.clone()
+                },
+                row.pane_id.clone(),
+                row.command.clone(),
+                row.mark().to_string(),
+                row.title.clone(),
+            ]
+        })
+        .collect();
+    let mut widths = header.map(str::len);
+    for line in &cells {
+        for (width, cell) in widths.iter_mut().zip(line) {
+            *width = (*width).max(cell.chars().count());
+        }
+    }
+    let print = |line: [&str; 8]| {
+        let mut out = String::new();
+        for (column, cell) in line.iter().enumerate() {
+            if column + 1 == line.len() {
+                out.push_str(cell);
+            } else {
+                let pad = widths[column] - cell.chars().count();
+                out.push_str(cell);
+                out.push_str(&" ".repeat(pad + 2));
+            }
+        }
+        println!("{}", out.trim_end());
+    };
+    print(header);
+    for line in &cells {
+        print(line.each_ref().map(String::as_str));
+    }
+}
@@ -1,86 +1,212 @@
+mod api;
+mod cli;
 mod config;
+mod dbus;
 mod doctor;
+mod events;
+mod hooks;
+mod inventory;
+mod list;
+mod migrate;
 mod model;
+mod notify;
+mod osc;
+mod panelog;
 mod poller;
+mod recorder;
+mod replay;
 mod setup;
 mod ssh;
+mod sshconfig;
+mod statefile;
 mod tmux;
 mod ui;
+mod validate;
+mod watch;
+mod webhook;
 
 use anyhow::{anyhow, Context, Result};
+use cli::Command;
 use config::Config;
 use crossterm::event::{Event, EventStream, KeyCode, KeyEventKind};
-use dialoguer::{theme::ColorfulTheme, Input};
+use dialoguer::{theme::ColorfulTheme, Input, MultiSelect};
 use futures_util::StreamExt;
 use model::{AppState, HostColors};
 use poller::PollerHandle;
 use ratatui::style::Color;
 use std::collections::HashMap;
-use std::path::Path;
+use std::io::IsTerminal;
+use std::path::{Path, PathBuf};
 use std::process::Stdio;
 use std::sync::Arc;
 use std::time::Duration;
 use tokio::sync::{mpsc, Mutex};
 
+const STATE_SAVE_INTERVAL: Duration = Duration::from_secs(30);
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
 enum Mode {
     Dashboard,
     Setup(setup::SetupState),
+    Replay(replay::ReplayState),
 }
 
 enum DashboardAction {
     None,
     Quit,
     OpenSetup,
+    OpenReplay,
+    Reloaded,
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
-    let config_path = config::config_path()?;
-    let args: Vec<String> = std::env::args().collect();
-    if args.len() > 1 && args[1] == "doctor" {
-        let config = config::load(&config_path)
-            .with_context(|| format!("Failed to load {}", config_path.display()))?;
-        doctor::run(&config).await?;
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let cli = match cli::parse(&args) {
+        Ok(cli) => cli,
+        Err(err) => {
+            eprintln!("error: {err}");
+            std::process::exit(2);
+        }
+    };
+    if let Command::Help(topic) = cli.command {
+        print!("{}", cli::help(topic));
         return Ok(());
     }
-    if args.len() > 1 && args[1] == "notify-test" {
-        let config = if config_path.exists() {
-            config::load(&config_path)?
-        } else {
-            Config::default()
-        };
-        println!("FleetMux notification test");
-        println!("- Ringing terminal bell...");
-        ui::bell()?;
-        if cfg!(target_os = "macos") {
-            println!("- Sending macOS notification...");
-            let sender = config.ui.macos_notify_sender.as_deref();
-            let _ = ui::notify_macos(
-                "FleetMux",
-                "Test notification from fleetmux",
-                sender,
-            );
-        } else {
-            println!("- macOS notifications not available on this OS.");
-        }
+    if let Command::Version = cli.command {
+        println!("fleetmux {}", env!("CARGO_PKG_VERSION"));
         return Ok(());
     }
+    let config_path = config::config_path(cli.config.as_deref(), cli.profile.as_deref())?;
+    let overrides = config::UiOverrides {
+        refresh_ms: cli.refresh_ms,
+        no_ansi: cli.no_ansi,
+    };
+    match cli.command {
+        Command::Dash => run_dashboard(config_path, overrides, false).await,
+        Command::Setup => run_dashboard(config_path, overrides, true).await,
+        Command::Doctor => run_doctor(&config_path, overrides).await,
+        Command::NotifyTest => run_notify_test(&config_path).await,
+        Command::List { json } => {
+            list::run(&load_fleet(&config_path, overrides).await?, json).await
+        }
+        Command::Capture { pane, lines } => {
+            run_capture(&load_fleet(&config_path, overrides).await?, &pane, lines).await
+        }
+        Command::Attach { pane } => {
+            run_attach(&load_fleet(&config_path, overrides).await?, &pane).await
+        }
+        Command::Watch { json } => {
+            let mut config = config::load(&config_path)
+                .with_context(|| format!("Failed to load {}", config_path.display()))?;
+            config.ui.overrides = overrides;
+            watch::run(config, &config_path, json).await
+        }
+        Command::Replay {
+            path,
+            speed,
+            idle_limit,
+        } => replay::play(&path, speed, idle_limit).await,
+        Command::ConfigCheck { path } => run_config(path, &config_path, false),
+        Command::ConfigMigrate { path } => run_config(path, &config_path, true),
+        Command::ImportSsh {
+            group,
+            all,
+            ssh_config,
+        } => run_hosts(group, all, ssh_config.as_deref(), &config_path),
+        Command::Help(_) | Command::Version => Ok(()),
+    }
+}
+
+async fn run_doctor(config_path: &Path, overrides: config::UiOverrides) -> Result<()> {
+    let mut config = config::load(config_path)
+        .with_context(|| format!("Failed to load {}", config_path.display()))?;
+    config.ui.overrides = overrides;
+    for error in inventory::load_all(&mut config).await {
+        println!("{error}");
+    }
+    doctor::run(&config).await
+}
 
+async fn run_notify_test(config_path: &Path) -> Result<()> {
+    let config = if config_path.exists() {
+        config::load(config_path)?
+    } else {
+        Config::default()
+    };
+    println!("FleetMux notification test");
+    println!("- Ringing terminal bell...");
+    ui::bell()?;
+    if cfg!(target_os = "macos") {
+        println!("- Sending macOS notification...");
+        let sender = config.ui.macos_notify_sender.as_deref();
+        let _ = ui::notify_macos(
+            "FleetMux",
+            "Test notification from fleetmux",
+            sender,
+        );
+    } else {
+        println!("- macOS notifications not available on this OS.");
+    }
+    if cfg!(target_os = "linux") {
+        println!("- Sending desktop notification over D-Bus...");
+        if let Err(err) = dbus::send_test(&config.ui).await {
+            println!("  failed: {err:#}");
+        }
+    }
+    println!("- Writing terminal notification ({})...", config.ui.terminal_notify_format);
+    if let Err(err) = osc::send_test(&config.ui) {
+        println!("  failed: {err:#}");
+    }
+    for webhook in &config.webhooks {
+        println!("- Posting to webhook {}...", webhook.display_name());
+        match webhook::send_test(webhook).await {
+            Ok(()) => println!("  ok"),
+            Err(err) => println!("  failed: {err:#}"),
+        }
+    }
+    Ok(())
+}
+
+async fn run_dashboard(
+    config_path: PathBuf,
+    overrides: config::UiOverrides,
+    open_setup: bool,
+) -> Result<()> {
     let mut config = if config_path.exists() {
         config::load(&config_path)?
     } else {
         Config::default()
     };
-    apply_local_host(&mut config);
+    config.ui.overrides = overrides;
+    config::apply_local_host(&mut config);
+    let inventory_errors = inventory::load_all(&mut config).await;
 
     let host_colors = build_host_colors(&config);
     let mut state = AppState::new(config.clone(), host_colors.clone());
+    let mut problems = config.warnings.clone();
+    problems.extend(inventory_errors);
+    match statefile::load(&config_path) {
+        Ok(saved) => saved.restore(&mut state),
+        Err(err) => problems.push(format!("{err:#}")),
+    }
+    if let Some(summary) = summarize_warnings(&problems) {
+        state.set_status(summary);
+    }
 
+    let mut recorder = recorder::Recorder::new(&config.record)?;
+    let mut pane_logger = panelog::PaneLogger::new(&config.logs)?;
+    let mut api = api::ApiServer::start(&config.api).await?;
+    let mut tracker = events::EventTracker::new(&state);
+    let mut router = notify::Router::new(&config, false)?;
     let resolver = Arc::new(Mutex::new(ssh::HostResolver::new()));
     let (update_tx, mut update_rx) = mpsc::channel(100);
     let mut pollers: Option<PollerHandle> = None;
+    let (inventory_tx, mut inventory_rx) = mpsc::channel(16);
+    let mut inventory = inventory::start(&config, inventory_tx);
+    let mut config_watcher = config::ConfigWatcher::new(&config_path);
 
-    let mut mode = if config.hosts.is_empty() || config.tracked.is_empty() {
+    let mut mode = if open_setup || config.hosts.is_empty() || config.tracked.is_empty() {
         Mode::Setup(setup::SetupState::new(config.clone()))
     } else {
         pollers = Some(poller::start_pollers(
@@ -94,6 +220,8 @@ async fn main() -> Result<()> {
     let mut terminal = ui::enter_terminal()?;
     let mut events = EventStream::new();
     let mut tick = tokio::time::interval(Duration::from_millis(200));
+    let mut save_tick = tokio::time::interval(STATE_SAVE_INTERVAL);
+    let mut config_tick = tokio::time::interval(CONFIG_POLL_INTERVAL);
 
     loop {
         match &mut mode {
@@ -104,13 +232,25 @@ async fn main() -> Result<()> {
                 setup_state.handle_messages();
                 terminal.draw(|f| setup_state.draw(f))?;
             }
+            Mode::Replay(replay_state) => {
+                replay_state.advance();
+                terminal.draw(|f| replay_state.draw(f))?;
+            }
         }
 
+        let mut transitions = None;
         tokio::select! {
             maybe_update = update_rx.recv() => {
                 if let Some(update) = maybe_update {
-                    if matches!(mode, Mode::Dashboard) {
-                        state.apply_update(update);
+                    if pollers.as_ref().is_some_and(|pollers| pollers.is_current(&update)) {
+                        let index = update.index;
+                        if state.apply_update(update) {
+                            record_pane(&mut recorder, &mut state, index);
+                            log_pane(&mut pane_logger, &mut state, index);
+                            if let Some(pane) = state.panes.get(index) {
+                                router.observe_output(pane);
+                            }
+                        }
                     }
                 }
             }
@@ -125,7 +265,6 @@ async fn main() -> Result<()> {
                                 &mut terminal,
                                 &resolver,
                                 pollers.as_mut(),
-                                &update_tx,
                                 &mut config,
                             ).await? {
                                 DashboardAction::Quit => break,
@@ -135,32 +274,53 @@ async fn main() -> Result<()> {
                                     }
                                     mode = Mode::Setup(setup::SetupState::new(config.clone()));
                                 }
+                                DashboardAction::OpenReplay => {
+                                    if let Some(replay_state) = open_replay_for_focused(&mut state) {
+                                        mode = Mode::Replay(replay_state);
+                                    }
+                                }
+                                DashboardAction::Reloaded => {
+                                    tracker = events::EventTracker::new(&state);
+                                    rebuild_router(&mut router, &mut state);
+                                    inventory.restart(&config);
+                                }
                                 DashboardAction::None => {}
                             }
+                            config_watcher.sync();
+                        }
+                        Mode::Replay(replay_state) => {
+                            if let replay::ReplayAction::Close = replay_state.handle_event(event) {
+                                mode = Mode::Dashboard;
+                            }
                         }
                         Mode::Setup(setup_state) => {
                             match setup_state.handle_event(event)? {
                                 setup::SetupAction::Save { config: new_config, tracked } => {
                                     let mut new_config = new_config.clone();
                                     new_config.tracked = tracked;
-                                    apply_local_host(&mut new_config);
+                                    config::apply_local_host(&mut new_config);
+                                    inventory::carry_over(&config, &mut new_config);
                                     config::save(&config_path, &new_config)?;
                                     config = new_config.clone();
-                                    let host_colors = build_host_colors(&new_config);
-                                    state = AppState::new(new_config.clone(), host_colors);
+                                    rebuild_state(&mut state, &new_config);
+                                    tracker = events::EventTracker::new(&state);
+                                    rebuild_router(&mut router, &mut state);
                                     pollers = Some(poller::start_pollers(
                                         &new_config,
                                         Arc::clone(&resolver),
                                         update_tx.clone(),
                                     ));
+                                    inventory.restart(&new_config);
+                                    config_watcher.sync();
                                     mode = Mode::Dashboard;
                                 }
                                 setup::SetupAction::Cancel => {
                                     if config.tracked.is_empty() {
                                         setup_state.set_status("Select panes and press 's' to save.");
                                     } else {
-                                        let host_colors = build_host_colors(&config);
-                                        state = AppState::new(config.clone(), host_colors);
+                                        rebuild_state(&mut state, &config);
+                                        tracker = events::EventTracker::new(&state);
+                                        rebuild_router(&mut router, &mut state);
                                         pollers = Some(poller::start_pollers(
                                             &config,
                                             Arc::clone(&resolver),
@@ -175,23 +335,92 @@ async fn main() -> Result<()> {
                     }
                 }
             }
-            _ = tick.tick() => {
+            Some(update) = inventory_rx.recv() => {
+                match update.result {
+                    Ok(hosts) => {
+                        if inventory::apply(&mut config, &update.source, hosts)
+                            && let Some(active) = pollers.as_mut()
+                        {
+                            rebuild_state(&mut state, &config);
+                            tracker = events::EventTracker::new(&state);
+                            rebuild_router(&mut router, &mut state);
+                            active.update(&config).await;
+                        }
+                    }
+                    Err(err) => state.set_status(format!("Inventory {}: {err:#}", update.source)),
+                }
+            }
+            Some(request) = api::next_request(api.as_mut()) => {
+                if let Some(api) = &api {
+                    api.respond(&state, request);
+                }
+            }
+            Some(tracked) = router.next_action() => {
                 if matches!(mode, Mode::Dashboard) {
-                    state.refresh_stale();
-                    let transitions = state.update_activity_states();
-                    let newly_attention =
-                        mark_attention_for_indices(&mut state, &transitions.stopped);
-                    clear_done_attention_for_indices(&mut state, &transitions.active);
-                    maybe_notify_attention(&mut state, &newly_attention)?;
+                    focus_tracked(&mut state, &tracked);
+                    acknowledge_focused(&mut state);
+                    take_control_for_tracked(&state.config, &resolver, &mut terminal, &tracked)
+                        .await?;
+                    set_notify_snooze(&mut state);
+                }
+            }
+            _ = tick.tick() => {
+                if !matches!(mode, Mode::Setup(_)) {
+                    transitions = Some(state.tick());
+                }
+            }
+            _ = config_tick.tick() => {
+                if config_watcher.poll()
+                    && matches!(mode, Mode::Dashboard)
+                    && let Some(active) = pollers.as_mut()
+                {
+                    match reload_config(&config_path, active, &mut state, &mut config).await {
+                        Ok(restarted) => {
+                            reload_success(&mut state, restarted);
+                            tracker = events::EventTracker::new(&state);
+                            rebuild_router(&mut router, &mut state);
+                            inventory.restart(&config);
+                        }
+                        Err(err) => state.set_status(reload_failure(&err)),
+                    }
+                }
+            }
+            _ = save_tick.tick() => {
+                if !matches!(mode, Mode::Setup(_)) {
+                    save_state(&mut state, &config_path);
                 }
             }
         }
+
+        if !matches!(mode, Mode::Setup(_)) {
+            let events = tracker.collect(&state, transitions.as_ref());
+            if let Some(api) = &api {
+                api.publish(&events);
+            }
+            for error in router.handle(&state, &events) {
+                state.set_status(error);
+            }
+        }
     }
 
+    save_state(&mut state, &config_path);
     ui::exit_terminal(&mut terminal)?;
     Ok(())
 }
 
+fn save_state(state: &mut AppState, config_path: &Path) {
+    if state.panes.is_empty() {
+        return;
+    }
+    if let Err(err) = statefile::save(config_path, &statefile::SavedState::capture(state)) {
+        state.set_status(format!("{err:#}"));
+    }
+}
+
+fn rebuild_state(state: &mut AppState, config: &Config) {
+    state.reconcile(config.clone(), build_host_colors(config));
+}
+
 async fn handle_dashboard_event(
     event: Event,
     state: &mut AppState,
@@ -199,7 +428,6 @@ async fn handle_dashboard_event(
     terminal: &mut ui::AppTerminal,
     resolver: &Arc<Mutex<ssh::HostResolver>>,
     pollers: Option<&mut PollerHandle>,
-    update_tx: &mpsc::Sender<model::PaneUpdate>,
     config: &mut Config,
 ) -> Result<DashboardAction> {
     match event {
@@ -208,34 +436,33 @@ async fn handle_dashboard_event(
             KeyCode::Char('s') => return Ok(DashboardAction::OpenSetup),
             KeyCode::Char('?') => state.show_help = !state.show_help,
             KeyCode::Char('z') => state.zoomed = !state.zoomed,
+            KeyCode::Char('p') => return Ok(DashboardAction::OpenReplay),
             KeyCode::Char('r') => {
                 let Some(pollers) = pollers else {
                     return Ok(DashboardAction::None);
                 };
-                reload_config(
-                    config_path,
-                    resolver,
-                    pollers,
-                    update_tx,
-                    state,
-                    config,
-                )
-                .await?;
+                match reload_config(config_path, pollers, state, config).await {
+                    Ok(restarted) => reload_success(state, restarted),
+                    Err(err) => {
+                        state.set_status(reload_failure(&err));
+                        return Ok(DashboardAction::None);
+                    }
+                }
+                return Ok(DashboardAction::Reloaded);
             }
             KeyCode::Char('e') => {
                 let Some(pollers) = pollers else {
                     return Ok(DashboardAction::None);
                 };
                 edit_config(config_path, terminal)?;
-                reload_config(
-                    config_path,
-                    resolver,
-                    pollers,
-                    update_tx,
-                    state,
-                    config,
-                )
-                .await?;
+                match reload_config(config_path, pollers, state, config).await {
+                    Ok(restarted) => reload_success(state, restarted),
+                    Err(err) => {
+                        state.set_status(reload_failure(&err));
+                        return Ok(DashboardAction::None);
+                    }
+                }
+                return Ok(DashboardAction::Reloaded);
             }
             KeyCode::Char('n') => {
                 set_label_for_focused(state, terminal, config_path)?;
@@ -248,10 +475,14 @@ async fn handle_dashboard_event(
                 toggle_bookmark_for_focused(state, config_path)?;
                 *config = state.config.clone();
             }
+            KeyCode::Char('m') => toggle_mute_for_focused(state, config_path, false),
+            KeyCode::Char('M') => toggle_mute_for_focused(state, config_path, true),
             KeyCode::Char('c') => {
                 state.config.ui.compact = !state.config.ui.compact;
             }
+            KeyCode::Char('a') => acknowledge_focused(state),
             KeyCode::Enter => {
+                acknowledge_focused(state);
                 take_control(state, resolver, terminal).await?;
                 set_notify_snooze(state);
             }
@@ -276,6 +507,263 @@ async fn handle_dashboard_event(
     Ok(DashboardAction::None)
 }
 
+fn rebuild_router(router: &mut notify::Router, state: &mut AppState) {
+    match notify::Router::new(&state.config, false) {
+        Ok(new_router) => *router = new_router,
+        Err(err) => state.set_status(format!("Notifications unchanged: {err:#}")),
+    }
+}
+
+fn record_pane(recorder: &mut Option<recorder::Recorder>, state: &mut AppState, index: usize) {
+    let Some(active) = recorder.as_mut() else {
+        return;
+    };
+    let Some(pane) = state.panes.get(index) else {
+        return;
+    };
+    if let Err(err) = active.record(pane) {
+        state.set_status(format!("Recording stopped: {err:#}"));
+        *recorder = None;
+    }
+}
+
+fn log_pane(logger: &mut Option<panelog::PaneLogger>, state: &mut AppState, index: usize) {
+    let Some(active) = logger.as_mut() else {
+        return;
+    };
+    let Some(pane) = state.panes.get(index) else {
+        return;
+    };
+    if let Err(err) = active.append(pane) {
+        state.set_status(format!("Pane logging stopped: {err:#}"));
+        *logger = None;
+    }
+}
+
+fn open_replay_for_focused(state: &mut AppState) -> Option<replay::ReplayState> {
+    let pane = state.panes.get(state.focused)?;
+    let path = match recorder::latest_recording(&state.config.record, &pane.tracked) {
+        Ok(Some(path)) => path,
+        Ok(None) => {
+            let message = if state.config.record.enabled {
+                "No recording for this pane yet."
+            } else {
+                "No recording for this pane (set record.enabled = true)."
+            };
+            state.set_status(message);
+            return None;
+        }
+        Err(err) => {
+            state.set_status(format!("Replay failed: {err:#}"));
+            return None;
+        }
+    };
+    match replay::ReplayState::open(&path) {
+        Ok(replay_state) => Some(replay_state),
+        Err(err) => {
+            state.set_status(format!("Replay failed: {err:#}"));
+            None
+        }
+    }
+}
+
+fn run_hosts(group: bool, all: bool, ssh_config: Option<&str>, config_path: &Path) -> Result<()> {
+    let ssh_path = match ssh_config {
+        Some(path) => config::expand_home(path),
+        None => sshconfig::default_path()?,
+    };
+    let mut config = if config_path.exists() {
+        config::load(config_path)?
+    } else {
+        Config::default()
+    };
+    let ssh_hosts = sshconfig::load(&ssh_path)?;
+    let candidates: Vec<config::HostConfig> = sshconfig::to_host_configs(&ssh_hosts, group)
+        .into_iter()
+        .filter(|host| !sshconfig::is_configured(&config, host))
+        .collect();
+    if candidates.is_empty() {
+        println!("No new hosts found in {}", ssh_path.display());
+        return Ok(());
+    }
+    let labels: Vec<String> = candidates
+        .iter()
+        .map(|host| sshconfig::describe(&ssh_hosts, host))
+        .collect();
+    let picked: Vec<usize> = if all {
+        (0..candidates.len()).collect()
+    } else {
+        MultiSelect::with_theme(&ColorfulTheme::default())
+            .with_prompt("Hosts to import (space to toggle, enter to confirm)")
+            .items(&labels)
+            .interact()?
+    };
+    if picked.is_empty() {
+        println!("Nothing imported.");
+        return Ok(());
+    }
+    for index in &picked {
+        let host = candidates[*index].clone();
+        println!("+ {}: {}", host.name, sshconfig::describe(&ssh_hosts, &host));
+        config.hosts.push(host);
+    }
+    config::save(config_path, &config)?;
+    println!("Imported {} host(s) into {}", picked.len(), config_path.display());
+    Ok(())
+}
+
+fn run_config(path: Option<PathBuf>, config_path: &Path, upgrade: bool) -> Result<()> {
+    let path = path.unwrap_or_else(|| config_path.to_path_buf());
+    let (problems, warnings) = config::check(&path)?;
+    if !upgrade {
+        for warning in &warnings {
+            eprintln!("warning: {warning}");
+        }
+    }
+    if problems.is_empty() {
+        if !upgrade {
+            println!("{}: ok", path.display());
+        } else if let Some((from, backup)) = migrate::upgrade_file(&path)? {
+            println!(
+                "{}: upgraded from version {from} to {}, original saved to {}",
+                path.display(),
+                migrate::CURRENT_VERSION,
+                backup.display()
+            );
+        } else {
+            println!(
+                "{}: already at version {}",
+                path.display(),
+                migrate::CURRENT_VERSION
+            );
+        }
+        return Ok(());
+    }
+    let mut count = 0;
+    for (file, diagnostics) in &problems {
+        for problem in diagnostics {
+            count += 1;
+            eprintln!(
+                "{}:{}:{}: error: {}",
+                file.display(),
+                problem.line,
+                problem.column,
+                problem.message
+            );
+        }
+    }
+    eprintln!("{count} problem{} found", if count == 1 { "" } else { "s" });
+    std::process::exit(1);
+}
+
+async fn load_fleet(config_path: &Path, overrides: config::UiOverrides) -> Result<Config> {
+    let mut config = config::load(config_path)
+        .with_context(|| format!("Failed to load {}", config_path.display()))?;
+    config.ui.overrides = overrides;
+    config::apply_local_host(&mut config);
+    match statefile::load(config_path) {
+        Ok(saved) => saved.edits.apply(&mut config),
+        Err(err) => eprintln!("warning: {err:#}; ignoring dashboard labels and bookmarks"),
+    }
+    for warning in &config.warnings {
+        eprintln!("warning: {warning}");
+    }
+    for error in inventory::load_all(&mut config).await {
+        eprintln!("{error}");
+    }
+    Ok(config)
+}
+
+async fn resolve_host(
+    config: &Config,
+    resolver: &Arc<Mutex<ssh::HostResolver>>,
+    host: &str,
+) -> Result<(String, config::SshConfig)> {
+    let host_cfg = config
+        .hosts
+        .iter()
+        .find(|candidate| candidate.name == host)
+        .ok_or_else(|| anyhow!("Unknown host: {host}"))?;
+    let ssh_cfg = config.ssh.for_host(host_cfg);
+    let target = resolver
+        .lock()
+        .await
+        .resolve_target(host_cfg, &ssh_cfg)
+        .await?;
+    Ok((target, ssh_cfg))
+}
+
+async fn find_pane(
+    config: &Config,
+    resolver: &Arc<Mutex<ssh::HostResolver>>,
+    pane: &cli::PaneRef,
+) -> Result<config::TrackedPane> {
+    let mut known = config.tracked.iter().chain(&config.bookmarks);
+    let (host, pane_id) = match pane {
+        cli::PaneRef::Label(label) => {
+            return known
+                .find(|tracked| {
+                    tracked
+                        .label
+                        .as_deref()
+                        .is_some_and(|candidate| candidate.eq_ignore_ascii_case(label))
+                })
+                .cloned()
+                .ok_or_else(|| {
+                    anyhow!("No tracked pane or bookmark is labelled {label:?} (pass <host> <pane-id> for other panes)")
+                });
+        }
+        cli::PaneRef::Pane { host, pane_id } => (host, pane_id),
+    };
+    if let Some(tracked) = known.find(|tracked| tracked.host == *host && tracked.pane_id == *pane_id) {
+        return Ok(tracked.clone());
+    }
+    let (target, ssh_cfg) = resolve_host(config, resolver, host).await?;
+    let info = tmux::list_panes(&target, &ssh_cfg)
+        .await?
+        .into_iter()
+        .find(|info| info.pane_id == *pane_id)
+        .ok_or_else(|| anyhow!("No pane {pane_id} on {host}"))?;
+    Ok(config::TrackedPane {
+        host: host.clone(),
+        session: info.session,
+        window: info.window,
+        pane_id: info.pane_id,
+        label: None,
+    })
+}
+
+async fn run_capture(config: &Config, pane: &cli::PaneRef, lines: Option<usize>) -> Result<()> {
+    let resolver = Arc::new(Mutex::new(ssh::HostResolver::new()));
+    let tracked = find_pane(config, &resolver, pane).await?;
+    let (target, ssh_cfg) = resolve_host(config, &resolver, &tracked.host).await?;
+    let lines = lines.unwrap_or(config.ui.lines);
+    let capture = tmux::capture_pane(
+        &target,
+        &tracked.pane_id,
+        lines,
+        config.ui.join_lines,
+        config.ui.ansi() && std::io::stdout().is_terminal(),
+        &ssh_cfg,
+    )
+    .await?;
+    let end = capture
+        .lines
+        .iter()
+        .rposition(|line| !line.trim().is_empty())
+        .map_or(0, |last| last + 1);
+    for line in &capture.lines[end.saturating_sub(lines)..end] {
+        println!("{line}");
+    }
+    Ok(())
+}
+
+async fn run_attach(config: &Config, pane: &cli::PaneRef) -> Result<()> {
+    let resolver = Arc::new(Mutex::new(ssh::HostResolver::new()));
+    let tracked = find_pane(config, &resolver, pane).await?;
+    run_attach_command(attach_command(config, &resolver, &tracked).await?).await
+}
+
 fn move_focus(state: &mut AppState, direction: FocusMove) {
     let count = state.panes.len();
     if count == 0 {
@@ -308,16 +796,23 @@ fn move_focus(state: &mut AppState, direction: FocusMove) {
     clear_attention_on_focus(state, prev);
 }
 
+fn focus_tracked(state: &mut AppState, tracked: &config::TrackedPane) {
+    if let Some(index) = state.pane_index(tracked) {
+        let prev = state.focused;
+        state.focused = index;
+        clear_attention_on_focus(state, prev);
+    }
+}
+
 fn clear_attention_on_focus(state: &mut AppState, prev: usize) {
     if state.focused == prev {
         return;
     }
-    if state.attention.len() != state.panes.len() {
-        state.attention = vec![model::AttentionState::None; state.panes.len()];
-    }
-    if let Some(flag) = state.attention.get_mut(state.focused) {
-        *flag = model::AttentionState::None;
-    }
+    acknowledge_focused(state);
+}
+
+fn acknowledge_focused(state: &mut AppState) {
+    state.set_attention(state.focused, model::AttentionState::None);
 }
 
 fn grid_dimensions(count: usize) -> (usize, usize) {
@@ -328,28 +823,60 @@ fn grid_dimensions(count: usize) -> (usize, usize) {
 
 async fn reload_config(
     config_path: &Path,
-    resolver: &Arc<Mutex<ssh::HostResolver>>,
     pollers: &mut PollerHandle,
-    update_tx: &mpsc::Sender<model::PaneUpdate>,
     state: &mut AppState,
     config: &mut Config,
-) -> Result<()> {
+) -> Result<usize> {
     let new_config = config::load(config_path)
         .with_context(|| format!("Failed to reload {}", config_path.display()))?;
-    if new_config.hosts.is_empty() {
+    if new_config.hosts.is_empty() && new_config.inventory.is_empty() {
         return Err(anyhow!("Config missing hosts"));
     }
 
     let mut new_config = new_config.clone();
-    apply_local_host(&mut new_config);
+    new_config.ui.overrides = config.ui.overrides;
+    config::apply_local_host(&mut new_config);
+    inventory::carry_over(config, &mut new_config);
 
-    pollers.stop().await;
-    config::save(config_path, &new_config)?;
     *config = new_config.clone();
-    let host_colors = build_host_colors(&new_config);
-    *state = AppState::new(new_config.clone(), host_colors);
-    *pollers = poller::start_pollers(&new_config, Arc::clone(resolver), update_tx.clone());
-    Ok(())
+    rebuild_state(state, &new_config);
+    Ok(pollers.update(&new_config).await)
+}
+
+fn reload_success(state: &mut AppState, restarted: usize) {
+    let mut message = match restarted {
+        0 => "Reloaded config".to_string(),
+        1 => "Reloaded config (1 poller restarted)".to_string(),
+        count => format!("Reloaded config ({count} pollers restarted)"),
+    };
+    if let Some(summary) = summarize_warnings(&state.config.warnings) {
+        message.push_str(". ");
+        message.push_str(&summary);
+    }
+    state.set_status(message);
+}
+
+// The status bar holds one message, so several warnings share it.
+fn summarize_warnings(warnings: &[String]) -> Option<String> {
+    match warnings {
+        [] => None,
+        [warning] => Some(warning.clone()),
+        _ => Some(format!(
+            "{} warnings: {}; run `fleetmux doctor` for details",
+            warnings.len(),
+            warnings.join("; ")
+        )),
+    }
+}
+
+fn reload_failure(err: &anyhow::Error) -> String {
+    let message = format!("{err:#}");
+    let lines: Vec<&str> = message
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    format!("Kept previous config. {}", lines.join("; "))
 }
 
 fn edit_config(path: &Path, terminal: &mut ui::AppTerminal) -> Result<()> {
@@ -377,14 +904,14 @@ fn set_label_for_focused(
         .panes
         .get(state.focused)
         .ok_or_else(|| anyhow!("No focused pane"))?;
-    let host = pane.tracked.host.clone();
-    let session = pane.tracked.session.clone();
-    let window = pane.tracked.window;
-    let pane_id = pane.tracked.pane_id.clone();
+    let tracked = pane.tracked.clone();
 
     ui::exit_terminal(terminal)?;
     let theme = ColorfulTheme::default();
-    let prompt = format!("Label for {host} {session}:{window} {pane_id}");
+    let prompt = format!(
+        "Label for {} {}:{} {}",
+        tracked.host, tracked.session, tracked.window, tracked.pane_id
+    );
     let label: String = Input::with_theme(&theme)
         .with_prompt(prompt)
         .allow_empty(true)
@@ -397,35 +924,10 @@ fn set_label_for_focused(
         Some(label)
     };
 
-    for tracked in &mut state.config.tracked {
-        if tracked.host == host
-            && tracked.session == session
-            && tracked.window == window
-            && tracked.pane_id == pane_id
-        {
-            tracked.label = label.clone();
-        }
-    }
-    for bookmark in &mut state.config.bookmarks {
-        if bookmark.host == host
-            && bookmark.session == session
-            && bookmark.window == window
-            && bookmark.pane_id == pane_id
-        {
-            bookmark.label = label.clone();
-        }
-    }
-    for pane_state in &mut state.panes {
-        if pane_state.tracked.host == host
-            && pane_state.tracked.session == session
-            && pane_state.tracked.window == window
-            && pane_state.tracked.pane_id == pane_id
-        {
-            pane_state.tracked.label = label.clone();
-        }
-    }
-
-    config::save(config_path, &state.config)?;
+    let mut edits = state.pane_edits.clone();
+    edits.set_label(&tracked, label);
+    state.apply_pane_edits(edits);
+    save_state(state, config_path);
     Ok(())
 }
 
@@ -434,162 +936,79 @@ fn toggle_bookmark_for_focused(state: &mut AppState, config_path: &Path) -> Resu
         .panes
         .get(state.focused)
         .ok_or_else(|| anyhow!("No focused pane"))?;
-    if let Some(index) = state.config.bookmarks.iter().position(|bookmark| {
-        bookmark.host == pane.tracked.host
-            && bookmark.session == pane.tracked.session
-            && bookmark.window == pane.tracked.window
-            && bookmark.pane_id == pane.tracked.pane_id
-    }) {
-        state.config.bookmarks.remove(index);
-    } else {
-        state.config.bookmarks.push(config::TrackedPane {
-            host: pane.tracked.host.clone(),
-            session: pane.tracked.session.clone(),
-            window: pane.tracked.window,
-            pane_id: pane.tracked.pane_id.clone(),
-            label: pane.tracked.label.clone(),
-        });
-    }
-    config::save(config_path, &state.config)?;
+    let tracked = pane.tracked.clone();
+    let bookmarked = state
+        .config
+        .bookmarks
+        .iter()
+        .any(|bookmark| bookmark.same_pane(&tracked));
+    let mut edits = state.pane_edits.clone();
+    edits.set_bookmarked(&tracked, !bookmarked);
+    state.apply_pane_edits(edits);
+    save_state(state, config_path);
     Ok(())
 }
 
+fn toggle_mute_for_focused(state: &mut AppState, config_path: &Path, host: bool) {
+    let Some(pane) = state.panes.get(state.focused) else {
+        return;
+    };
+    let tracked = pane.tracked.clone();
+    let (name, muted) = if host {
+        let muted = if let Some(index) = state
+            .muted_hosts
+            .iter()
+            .position(|muted| *muted == tracked.host)
+        {
+            state.muted_hosts.remove(index);
+            false
+        } else {
+            state.muted_hosts.push(tracked.host.clone());
+            true
+        };
+        (format!("host {}", tracked.host), muted)
+    } else {
+        let muted = if let Some(index) = state
+            .muted_panes
+            .iter()
+            .position(|muted| muted.same_pane(&tracked))
+        {
+            state.muted_panes.remove(index);
+            false
+        } else {
+            state.muted_panes.push(tracked.clone());
+            true
+        };
+        (
+            format!(
+                "{} {}:{} {}",
+                tracked.host, tracked.session, tracked.window, tracked.pane_id
+            ),
+            muted,
+        )
+    };
+    state.set_status(if muted {
+        format!("Muted {name}")
+    } else {
+        format!("Unmuted {name}")
+    });
+    save_state(state, config_path);
+}
+
 fn toggle_attention_for_focused(state: &mut AppState) {
     let count = state.panes.len();
     if count == 0 {
         return;
     }
-    if state.attention.len() != count {
-        state.attention = vec![model::AttentionState::None; count];
-    }
+    state.ensure_attention_len();
     let index = state.focused.min(count - 1);
-    if let Some(flag) = state.attention.get_mut(index) {
-        *flag = match *flag {
-            model::AttentionState::None => model::AttentionState::Manual,
-            model::AttentionState::Manual | model::AttentionState::Done => {
-                model::AttentionState::None
-            }
-        };
-    }
-}
-
-fn mark_attention_for_indices(state: &mut AppState, indices: &[usize]) -> Vec<usize> {
-    if state.attention.len() != state.panes.len() {
-        state.attention = vec![model::AttentionState::None; state.panes.len()];
-    }
-    let mut newly = Vec::new();
-    for &index in indices {
-        if let Some(flag) = state.attention.get_mut(index) {
-            if *flag != model::AttentionState::Done {
-                *flag = model::AttentionState::Done;
-                newly.push(index);
-            }
-        }
-    }
-    newly
-}
-
-fn clear_done_attention_for_indices(state: &mut AppState, indices: &[usize]) {
-    if state.attention.len() != state.panes.len() {
-        state.attention = vec![model::AttentionState::None; state.panes.len()];
-    }
-    for &index in indices {
-        if let Some(flag) = state.attention.get_mut(index) {
-            if *flag == model::AttentionState::Done {
-                *flag = model::AttentionState::None;
-            }
-        }
-    }
-}
-
-fn maybe_notify_attention(state: &mut AppState, indices: &[usize]) -> Result<()> {
-    if indices.is_empty() {
-        return Ok(());
-    }
-    let now = std::time::Instant::now();
-    let snoozed = state
-        .notify_snooze_until
-        .map(|until| now < until)
-        .unwrap_or(false);
-    if snoozed {
-        if let Some(until) = state.notify_snooze_until {
-            if now >= until {
-                state.notify_snooze_until = None;
-            }
-        }
-        return Ok(());
-    }
-    if state.config.ui.bell_on_stop {
-        ui::bell()?;
-    }
-    if state.config.ui.macos_notification_on_stop {
-        let mut allow_notify = true;
-        if state.config.ui.macos_notify_only_when_inactive {
-            allow_notify = match ui::macos_frontmost_app() {
-                Ok(Some(app)) => !state
-                    .config
-                    .ui
-                    .macos_notify_ignore_apps
-                    .iter()
-                    .any(|name| name.eq_ignore_ascii_case(&app)),
-                _ => true,
-            };
-        }
-        if allow_notify {
-            let message = attention_message(state, indices);
-            let sender = state.config.ui.macos_notify_sender.as_deref();
-            let _ = ui::notify_macos("FleetMux", &message, sender);
-        }
-    }
-    Ok(())
-}
-
-fn attention_message(state: &AppState, indices: &[usize]) -> String {
-    let max = 3usize;
-    let mut lines = Vec::new();
-    for &index in indices.iter().take(max) {
-        if let Some(pane) = state.panes.get(index) {
-            let label = pane
-                .tracked
-                .label
-                .as_deref()
-                .filter(|value| !value.is_empty())
-                .map(|value| value.to_string())
-                .or_else(|| {
-                    pane.last_capture.as_ref().and_then(|capture| {
-                        if !capture.title.is_empty() {
-                            Some(capture.title.clone())
-                        } else if !capture.command.is_empty() {
-                            Some(capture.command.clone())
-                        } else {
-                            None
-                        }
-                    })
-                })
-                .unwrap_or_else(|| format_pane_id(&pane.tracked.pane_id));
-            let session_window = format!("{}:{}", pane.tracked.session, pane.tracked.window);
-            lines.push(format!(
-                "{} {} {}",
-                pane.tracked.host, session_window, label
-            ));
-        }
-    }
-    let extra = indices.len().saturating_sub(max);
-    if extra > 0 {
-        lines.push(format!("+{extra} more"));
-    }
-    if indices.len() == 1 {
-        format!("Pane done:\n{}", lines.join("\n"))
-    } else {
-        format!("Panes done ({})\n{}", indices.len(), lines.join("\n"))
-    }
-}
-
-fn format_pane_id(pane_id: &str) -> String {
-    pane_id
-        .strip_prefix('%')
-        .map(|id| format!("pane {id}"))
-        .unwrap_or_else(|| format!("pane {pane_id}"))
+    let next = match state.attention[index] {
+        model::AttentionState::None => model::AttentionState::Manual,
+        model::AttentionState::Manual
+        | model::AttentionState::Done
+        | model::AttentionState::Input => model::AttentionState::None,
+    };
+    state.set_attention(index, next);
 }
 
 fn bookmark_index_from_key(ch: char) -> Option<usize> {
@@ -627,16 +1046,19 @@ async fn take_control_for_tracked(
     terminal: &mut ui::AppTerminal,
     tracked: &config::TrackedPane,
 ) -> Result<()> {
-    let host_cfg = config
-        .hosts
-        .iter()
-        .find(|host| host.name == tracked.host)
-        .ok_or_else(|| anyhow!("Unknown host: {}", tracked.host))?;
+    let attach = attach_command(config, resolver, tracked).await?;
+    ui::exit_terminal(terminal)?;
+    run_attach_command(attach).await?;
+    *terminal = ui::enter_terminal()?;
+    Ok(())
+}
 
-    let target = {
-        let mut resolver = resolver.lock().await;
-        resolver.resolve_target(host_cfg, &config.ssh).await
-    }?;
+async fn attach_command(
+    config: &Config,
+    resolver: &Arc<Mutex<ssh::HostResolver>>,
+    tracked: &config::TrackedPane,
+) -> Result<(tokio::process::Command, &'static str)> {
+    let (target, ssh_cfg) = resolve_host(config, resolver, &tracked.host).await?;
 
     let remote_cmd = format!(
         "tmux attach -t {session} \\; select-window -t {session}:{window} \\; select-pane -t {pane_id}",
@@ -644,62 +1066,36 @@ async fn take_control_for_tracked(
         window = tracked.window,
         pane_id = tracked.pane_id
     );
-    let remote_cmd = ssh::wrap_remote_cmd(&config.ssh, &remote_cmd);
-
-    ui::exit_terminal(terminal)?;
+    let remote_cmd = ssh::wrap_remote_cmd(&ssh_cfg, &remote_cmd);
 
     if ssh::is_local_target(&target) {
-        let local_cmd = ssh::wrap_remote_cmd(&config.ssh, &remote_cmd);
-        let status = tokio::process::Command::new("sh")
-            .arg("-lc")
-            .arg(local_cmd)
-            .stdin(Stdio::inherit())
-            .stdout(Stdio::inherit())
-            .stderr(Stdio::inherit())
-            .status()
-            .await
-            .context("Failed to launch tmux")?;
-        if !status.success() {
-            eprintln!("tmux exited with status {status}");
-        }
-    } else {
-        let mut cmd = tokio::process::Command::new("ssh");
-        cmd.arg("-t");
-        for arg in ssh::build_ssh_args(&config.ssh) {
-            cmd.arg(arg);
-        }
-        cmd.arg(&target);
-        cmd.arg(remote_cmd);
-        cmd.stdin(Stdio::inherit());
-        cmd.stdout(Stdio::inherit());
-        cmd.stderr(Stdio::inherit());
-
-        let status = cmd.status().await.context("Failed to launch ssh")?;
-        if !status.success() {
-            eprintln!("ssh exited with status {status}");
-        }
+        let local_cmd = ssh::wrap_remote_cmd(&ssh_cfg, &remote_cmd);
+        let mut cmd = tokio::process::Command::new("sh");
+        cmd.arg("-lc").arg(local_cmd);
+        return Ok((cmd, "tmux"));
     }
-
-    *terminal = ui::enter_terminal()?;
-    Ok(())
+    let mut cmd = tokio::process::Command::new("ssh");
+    cmd.arg("-t");
+    for arg in ssh::build_ssh_args(&ssh_cfg) {
+        cmd.arg(arg);
+    }
+    cmd.arg(&target);
+    cmd.arg(remote_cmd);
+    Ok((cmd, "ssh"))
 }
 
-fn apply_local_host(config: &mut Config) {
-    if !config.local.enabled {
-        return;
-    }
-    let name = config.local.name.trim();
-    let name = if name.is_empty() { "local" } else { name };
-    if config.hosts.iter().any(|host| host.name == name) {
-        return;
+async fn run_attach_command((mut cmd, program): (tokio::process::Command, &str)) -> Result<()> {
+    let status = cmd
+        .stdin(Stdio::inherit())
+        .stdout(Stdio::inherit())
+        .stderr(Stdio::inherit())
+        .status()
+        .await
+        .with_context(|| format!("Failed to launch {program}"))?;
+    if !status.success() {
+        eprintln!("{program} exited with status {status}");
     }
-    config.hosts.push(config::HostConfig {
-        name: name.to_string(),
-        targets: vec!["local".to_string()],
-        strategy: Some("local".to_string()),
-        color: config.local.color.clone(),
-        tags: Some(vec!["local".to_string()]),
-    });
+    Ok(())
 }
 
 fn build_host_colors(config: &Config) -> HashMap<String, HostColors> {
@@ -709,7 +1105,7 @@ fn build_host_colors(config: &Config) -> HashMap<String, HostColors> {
             .color
             .clone()
             .unwrap_or_else(|| deterministic_color_name(&host.name, &config.colors.default_host_palette));
-        let base_color = parse_color(&color_name).unwrap_or(Color::Blue);
+        let base_color = model::parse_color(&color_name).unwrap_or(Color::Blue);
         let focus = focus_color(base_color);
         map.insert(
             host.name.clone(),
@@ -740,28 +1136,6 @@ fn fnv1a(bytes: &[u8]) -> u64 {
     hash
 }
 
-fn parse_color(name: &str) -> Option<Color> {
-    match name.to_lowercase().as_str() {
-        "black" => Some(Color::Black),
-        "red" => Some(Color::Red),
-        "green" => Some(Color::Green),
-        "yellow" => Some(Color::Yellow),
-        "blue" => Some(Color::Blue),
-        "magenta" => Some(Color::Magenta),
-        "cyan" => Some(Color::Cyan),
-        "gray" | "grey" => Some(Color::Gray),
-        "darkgray" | "darkgrey" => Some(Color::DarkGray),
-        "lightred" => Some(Color::LightRed),
-        "lightgreen" => Some(Color::LightGreen),
-        "lightyellow" => Some(Color::LightYellow),
-        "lightblue" => Some(Color::LightBlue),
-        "lightmagenta" => Some(Color::LightMagenta),
-        "lightcyan" => Some(Color::LightCyan),
-        "white" => Some(Color::White),
-        _ => None,
-    }
-}
-
 fn focus_color(color: Color) -> Color {
     match color {
         Color::Blue => Color::LightBlue,
@@ -0,0 +1,169 @@
+use anyhow::{anyhow, Context, Result};
+use std::fs;
+use std::path::{Path, PathBuf};
+use toml_edit::{DocumentMut, Item, TableLike, Value};
+
+pub const CURRENT_VERSION: u32 = 2;
+
+// MIGRATIONS[n] upgrades a version n + 1 document to version n + 2.
+const MIGRATIONS: &[fn(&mut DocumentMut)] = &[v1_to_v2];
+
+const DEPRECATED: &[(&str, &str, &str)] = &[
+    ("ui", "layout", "tiles are always laid out automatically"),
+    ("ui", "theme", "use [colors] and per-host `color` instead"),
+    (
+        "hosts",
+        "strategy",
+        "local hosts are detected from targets = [\"local\"]",
+    ),
+];
+
+pub struct Migrated {
+    pub contents: String,
+    pub from: Option<u32>,
+    pub warnings: Vec<String>,
+}
+
+pub fn migrate(contents: &str) -> Result<Migrated> {
+    let unchanged = |warnings| Migrated {
+        contents: contents.to_string(),
+        from: None,
+        warnings,
+    };
+    // Syntax errors are reported by validation with proper locations.
+    let Ok(mut document) = contents.parse::<DocumentMut>() else {
+        return Ok(unchanged(Vec::new()));
+    };
+    let version = match document.get("version") {
+        None => 1,
+        Some(item) => item
+            .as_integer()
+            .and_then(|version| u32::try_from(version).ok())
+            .filter(|version| *version >= 1)
+            .ok_or_else(|| anyhow!("version must be a positive integer"))?,
+    };
+    if version > CURRENT_VERSION {
+        return Err(anyhow!(
+            "config version {version} is newer than this fleetmux supports ({CURRENT_VERSION}); upgrade fleetmux to use it"
+        ));
+    }
+    let warnings = deprecated(&document)
+        .into_iter()
+        .map(|(key, note)| format!("`{key}` is deprecated and ignored: {note}"))
+        .collect();
+    if version == CURRENT_VERSION {
+        return Ok(unchanged(warnings));
+    }
+    for migration in &MIGRATIONS[version as usize - 1..] {
+        migration(&mut document);
+    }
+    document.remove("version");
+    Ok(Migrated {
+        contents: stamp_version(&document.to_string()),
+        from: Some(version),
+        warnings,
+    })
+}
+
+// Goes below a leading comment block that is separated from the rest of the
+// file, so header comments stay at the top.
+fn stamp_version(contents: &str) -> String {
+    let mut offset = 0;
+    let mut header = 0;
+    for line in contents.split_inclusive('\n') {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            offset += line.len();
+            header = offset;
+        } else if trimmed.starts_with('#') {
+            offset += line.len();
+        } else {
+            break;
+        }
+    }
+    let (head, rest) = contents.split_at(header);
+    format!("{head}version = {CURRENT_VERSION}\n\n{}", rest.trim_start_matches('\n'))
+}
+
+// Only called from `config migrate` and when saving; loading migrates in
+// memory and leaves the file alone.
+pub fn upgrade_file(path: &Path) -> Result<Option<(u32, PathBuf)>> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Unable to read config file: {}", path.display()))?;
+    let migrated = migrate(&contents).with_context(|| path.display().to_string())?;
+    let Some(from) = migrated.from else {
+        return Ok(None);
+    };
+    let backup = backup(path, &contents, from)?;
+    fs::write(path, &migrated.contents)
+        .with_context(|| format!("Unable to write upgraded config: {}", path.display()))?;
+    Ok(Some((from, backup)))
+}
+
+pub fn backup(path: &Path, contents: &str, version: u32) -> Result<PathBuf> {
+    let backup = backup_path(path, version);
+    fs::write(&backup, contents)
+        .with_context(|| format!("Unable to write config backup: {}", backup.display()))?;
+    Ok(backup)
+}
+
+fn backup_path(path: &Path, version: u32) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(format!(".v{version}.bak"));
+    path.with_file_name(name)
+}
+
+fn deprecated(document: &DocumentMut) -> Vec<(String, &'static str)> {
+    DEPRECATED
+        .iter()
+        .filter(|(path, key, _)| {
+            document
+                .get(path)
+                .is_some_and(|item| sections(item).iter().any(|table| table.contains_key(key)))
+        })
+        .map(|(path, key, note)| (format!("{path}.{key}"), *note))
+        .collect()
+}
+
+fn sections(item: &Item) -> Vec<&dyn TableLike> {
+    match item {
+        Item::ArrayOfTables(array) => array.iter().map(|table| table as &dyn TableLike).collect(),
+        Item::Value(Value::Array(array)) => array
+            .iter()
+            .filter_map(Value::as_inline_table)
+            .map(|table| table as &dyn TableLike)
+            .collect(),
+        item => item.as_table_like().into_iter().collect(),
+    }
+}
+
+fn remove_key(document: &mut DocumentMut, path: &str, key: &str) {
+    let Some(item) = document.get_mut(path) else {
+        return;
+    };
+    match item {
+        Item::ArrayOfTables(array) => {
+            for table in array.iter_mut() {
+                table.remove(key);
+            }
+        }
+        Item::Value(Value::Array(array)) => {
+            for value in array.iter_mut() {
+                if let Some(table) = value.as_inline_table_mut() {
+                    table.remove(key);
+                }
+            }
+        }
+        item => {
+            if let Some(table) = item.as_table_like_mut() {
+                table.remove(key);
+            }
+        }
+    }
+}
+
+fn v1_to_v2(document: &mut DocumentMut) {
+    remove_key(document, "ui", "layout");
+    remove_key(document, "ui", "theme");
+    remove_key(document, "hosts", "strategy");
+}
@@ -1,15 +1,38 @@
 use crate::config::{Config, TrackedPane};
 use ratatui::style::Color;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::time::{Duration, Instant};
 
-
 #[derive(Clone, Debug)]
 pub struct HostColors {
     pub base: Color,
     pub focus: Color,
 }
 
+pub fn parse_color(name: &str) -> Option<Color> {
+    match name.to_lowercase().as_str() {
+        "black" => Some(Color::Black),
+        "red" => Some(Color::Red),
+        "green" => Some(Color::Green),
+        "yellow" => Some(Color::Yellow),
+        "blue" => Some(Color::Blue),
+        "magenta" => Some(Color::Magenta),
+        "cyan" => Some(Color::Cyan),
+        "gray" | "grey" => Some(Color::Gray),
+        "darkgray" | "darkgrey" => Some(Color::DarkGray),
+        "lightred" => Some(Color::LightRed),
+        "lightgreen" => Some(Color::LightGreen),
+        "lightyellow" => Some(Color::LightYellow),
+        "lightblue" => Some(Color::LightBlue),
+        "lightmagenta" => Some(Color::LightMagenta),
+        "lightcyan" => Some(Color::LightCyan),
+        "white" => Some(Color::White),
+        _ => None,
+    }
+}
+
 pub fn default_host_colors() -> HostColors {
     HostColors {
         base: Color::Blue,
@@ -17,32 +40,76 @@ pub fn default_host_colors() -> HostColors {
     }
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct PaneCapture {
     pub command: String,
     pub title: String,
+    pub width: u16,
+    pub height: u16,
+    pub dead: bool,
+    pub dead_status: Option<i32>,
+    pub exit_status: Option<i32>,
     pub lines: Vec<String>,
 }
 
-#[derive(Clone, Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, Serialize)]
+pub struct CommandFinished {
+    pub command: String,
+    pub exit_status: Option<i32>,
+    pub duration_sec: u64,
+    #[serde(skip)]
+    pub at: Instant,
+}
+
+impl CommandFinished {
+    pub fn describe(&self) -> String {
+        match self.exit_status {
+            Some(code) => format!("{} finished, exit {code}", self.command),
+            None => format!("{} finished", self.command),
+        }
+    }
+}
+
+const SHELLS: &[&str] = &[
+    "sh", "bash", "zsh", "fish", "dash", "ksh", "mksh", "tcsh", "csh", "nu", "xonsh", "elvish",
+];
+
+pub fn is_shell(command: &str) -> bool {
+    let name = command.trim().trim_start_matches('-');
+    let name = name.rsplit('/').next().unwrap_or(name);
+    SHELLS.contains(&name)
+}
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum PaneStatus {
     Ok,
     Down,
     Stale,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
 pub enum ActivityState {
     Active,
     Idle,
     Quiet,
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum AttentionState {
     None,
     Manual,
     Done,
+    Input,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Escalation {
+    Renotify(Duration),
+    Escalate(Duration),
+    Escalated,
 }
 
 pub struct ActivityTransitions {
@@ -71,9 +138,11 @@ pub fn activity_state(
 #[derive(Clone, Debug)]
 pub struct PaneUpdate {
     pub index: usize,
+    pub poller: u64,
     pub capture: Option<PaneCapture>,
     pub status: PaneStatus,
     pub error: Option<String>,
+    pub target: Option<String>,
     pub at: Instant,
 }
 
@@ -86,6 +155,9 @@ pub struct PaneState {
     pub last_change: Option<Instant>,
     pub error: Option<String>,
     pub last_hash: Option<u64>,
+    pub running: Option<(String, Instant)>,
+    pub ended: Option<Instant>,
+    pub finished: Option<CommandFinished>,
 }
 
 impl PaneState {
@@ -98,9 +170,57 @@ impl PaneState {
             last_change: None,
             error: None,
             last_hash: None,
+            running: None,
+            ended: None,
+            finished: None,
         }
     }
 
+    fn track_command(&mut self, capture: &PaneCapture, at: Instant) -> bool {
+        let command = capture.command.trim();
+        let busy = !capture.dead && !command.is_empty() && !is_shell(command);
+        if busy {
+            if self.running.is_none() {
+                self.running = Some((command.to_string(), at));
+                self.finished = None;
+            }
+            self.ended = None;
+            return false;
+        }
+        if self.running.is_none() {
+            return false;
+        }
+        // The prompt hook sets @fleetmux_exit after the shell is back in the
+        // foreground, so read the status one poll later.
+        let Some(ended) = self.ended else {
+            if !capture.dead {
+                self.ended = Some(at);
+                return false;
+            }
+            return self.finish(capture.dead_status, at, at);
+        };
+        let exit_status = if capture.dead {
+            capture.dead_status
+        } else {
+            capture.exit_status
+        };
+        self.finish(exit_status, ended, at)
+    }
+
+    fn finish(&mut self, exit_status: Option<i32>, ended: Instant, at: Instant) -> bool {
+        let Some((command, started)) = self.running.take() else {
+            return false;
+        };
+        self.ended = None;
+        self.finished = Some(CommandFinished {
+            command,
+            exit_status,
+            duration_sec: ended.duration_since(started).as_secs(),
+            at,
+        });
+        true
+    }
+
     pub fn activity_state(
         &self,
         active_window: Duration,
@@ -113,6 +233,57 @@ impl PaneState {
     }
 }
 
+// Labels and bookmarks changed from the dashboard. They are kept in the state
+// file instead of the config and layered over it after every load.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PaneEdits {
+    pub labels: Vec<TrackedPane>,
+    pub bookmarks: Vec<TrackedPane>,
+    pub removed_bookmarks: Vec<TrackedPane>,
+}
+
+impl PaneEdits {
+    pub fn apply(&self, config: &mut Config) {
+        config.bookmarks.retain(|bookmark| {
+            !self
+                .removed_bookmarks
+                .iter()
+                .any(|removed| removed.same_pane(bookmark))
+        });
+        for bookmark in &self.bookmarks {
+            if !config.bookmarks.iter().any(|known| known.same_pane(bookmark)) {
+                config.bookmarks.push(bookmark.clone());
+            }
+        }
+        for edit in &self.labels {
+            for pane in config.tracked.iter_mut().chain(&mut config.bookmarks) {
+                if pane.same_pane(edit) {
+                    pane.label = edit.label.clone();
+                }
+            }
+        }
+    }
+
+    pub fn set_label(&mut self, pane: &TrackedPane, label: Option<String>) {
+        self.labels.retain(|edit| !edit.same_pane(pane));
+        self.labels.push(TrackedPane {
+            label,
+            ..pane.clone()
+        });
+    }
+
+    pub fn set_bookmarked(&mut self, pane: &TrackedPane, bookmarked: bool) {
+        self.bookmarks.retain(|bookmark| !bookmark.same_pane(pane));
+        self.removed_bookmarks.retain(|removed| !removed.same_pane(pane));
+        if bookmarked {
+            self.bookmarks.push(pane.clone());
+        } else {
+            self.removed_bookmarks.push(pane.clone());
+        }
+    }
+}
+
 #[derive(Debug)]
 pub struct AppState {
     pub config: Config,
@@ -124,6 +295,13 @@ pub struct AppState {
     pub activity_states: Vec<ActivityState>,
     pub notify_snooze_until: Option<Instant>,
     pub attention: Vec<AttentionState>,
+    pub attention_since: Vec<Option<Instant>>,
+    pub host_targets: HashMap<String, Option<String>>,
+    pub status_message: Option<(String, Instant)>,
+    pub input_patterns: Vec<Regex>,
+    pub muted_hosts: Vec<String>,
+    pub muted_panes: Vec<TrackedPane>,
+    pub pane_edits: PaneEdits,
 }
 
 impl AppState {
@@ -140,6 +318,7 @@ impl AppState {
             .iter()
             .map(|pane| pane.activity_state(active_window, idle_after))
             .collect();
+        let input_patterns = compile_patterns(&config.notifications.input_patterns);
         Self {
             config,
             panes,
@@ -150,11 +329,84 @@ impl AppState {
             activity_states,
             notify_snooze_until: None,
             attention: vec![AttentionState::None; pane_count],
+            attention_since: vec![None; pane_count],
+            host_targets: HashMap::new(),
+            status_message: None,
+            input_patterns,
+            muted_hosts: Vec::new(),
+            muted_panes: Vec::new(),
+            pane_edits: PaneEdits::default(),
+        }
+    }
+
+    pub fn reconcile(&mut self, mut config: Config, host_colors: HashMap<String, HostColors>) {
+        self.pane_edits.apply(&mut config);
+        self.ensure_attention_len();
+        let focused = self.panes.get(self.focused).map(|pane| pane.tracked.clone());
+        let mut old: Vec<Option<(PaneState, AttentionState, Option<Instant>)>> = self
+            .panes
+            .drain(..)
+            .zip(self.attention.drain(..))
+            .zip(self.attention_since.drain(..))
+            .map(|((pane, attention), since)| Some((pane, attention, since)))
+            .collect();
+        let old_hosts = std::mem::take(&mut self.config.hosts);
+        let host_unchanged = |host: &str| {
+            old_hosts.iter().find(|config| config.name == host)
+                == config.hosts.iter().find(|config| config.name == host)
+        };
+        for tracked in &config.tracked {
+            let kept = old
+                .iter()
+                .position(|entry| {
+                    entry
+                        .as_ref()
+                        .is_some_and(|(pane, _, _)| pane.tracked.same_pane(tracked))
+                })
+                .filter(|_| host_unchanged(&tracked.host))
+                .and_then(|index| old[index].take());
+            let (mut pane, attention, since) = kept.unwrap_or_else(|| {
+                (PaneState::new(tracked.clone()), AttentionState::None, None)
+            });
+            pane.tracked = tracked.clone();
+            self.panes.push(pane);
+            self.attention.push(attention);
+            self.attention_since.push(since);
         }
+        self.host_targets.retain(|host, _| {
+            host_unchanged(host) && config.hosts.iter().any(|config| &config.name == host)
+        });
+        let (active_window, idle_after) = config.ui.activity_windows();
+        self.activity_states = self
+            .panes
+            .iter()
+            .map(|pane| pane.activity_state(active_window, idle_after))
+            .collect();
+        self.input_patterns = compile_patterns(&config.notifications.input_patterns);
+        self.host_colors = host_colors;
+        self.config = config;
+        self.focused = focused
+            .and_then(|tracked| self.pane_index(&tracked))
+            .unwrap_or(0)
+            .min(self.panes.len().saturating_sub(1));
     }
 
-    pub fn apply_update(&mut self, update: PaneUpdate) {
+    pub fn apply_pane_edits(&mut self, edits: PaneEdits) {
+        self.pane_edits = edits;
+        self.pane_edits.apply(&mut self.config);
+        for (pane, tracked) in self.panes.iter_mut().zip(&self.config.tracked) {
+            pane.tracked.label = tracked.label.clone();
+        }
+    }
+
+    pub fn apply_update(&mut self, update: PaneUpdate) -> bool {
+        let mut changed = false;
+        let mut finished = false;
         if let Some(pane) = self.panes.get_mut(update.index) {
+            if self.config.hosts.iter().any(|host| host.name == pane.tracked.host) {
+                self.host_targets
+                    .insert(pane.tracked.host.clone(), update.target.clone());
+            }
             pane.status = update.status;
             pane.error = update.error;
             pane.last_update = Some(update.at);
@@ -162,15 +414,33 @@ impl AppState {
                 let new_hash = hash_capture(&capture);
                 if pane.last_hash.map(|h| h != new_hash).unwrap_or(true) {
                     pane.last_change = Some(update.at);
+                    changed = true;
                 }
                 pane.last_hash = Some(new_hash);
+                finished = pane.track_command(&capture, update.at);
                 pane.last_capture = Some(capture);
             }
         }
+        if finished {
+            self.mark_done_attention(&[update.index]);
+        }
+        changed
+    }
+
+    pub fn set_status(&mut self, message: impl Into<String>) {
+        self.status_message = Some((message.into(), Instant::now()));
+    }
+
+    pub fn current_status(&self) -> Option<&str> {
+        let visible_for = Duration::from_secs(5);
+        self.status_message
+            .as_ref()
+            .filter(|(_, at)| at.elapsed() <= visible_for)
+            .map(|(message, _)| message.as_str())
     }
 
     pub fn refresh_stale(&mut self) {
-        let stale_after = Duration::from_millis(self.config.ui.refresh_ms.saturating_mul(2));
+        let stale_after = self.config.ui.refresh().saturating_mul(2);
         let now = Instant::now();
         for pane in &mut self.panes {
             if matches!(pane.status, PaneStatus::Down) {
@@ -211,6 +481,139 @@ impl AppState {
         ActivityTransitions { stopped, active }
     }
 
+    pub fn tick(&mut self) -> ActivityTransitions {
+        self.refresh_stale();
+        let transitions = self.update_activity_states();
+        self.mark_done_attention(&transitions.stopped);
+        self.clear_done_attention(&transitions.active);
+        transitions
+    }
+
+    pub fn pane_index(&self, tracked: &TrackedPane) -> Option<usize> {
+        self.panes
+            .iter()
+            .position(|pane| pane.tracked.same_pane(tracked))
+    }
+
+    pub fn host_dynamic(&self, host: &str) -> bool {
+        self.config
+            .hosts
+            .iter()
+            .any(|config| config.name == host && config.source.is_some())
+    }
+
+    pub fn host_muted(&self, host: &str) -> bool {
+        self.muted_hosts.iter().any(|muted| muted == host)
+    }
+
+    pub fn pane_muted(&self, tracked: &TrackedPane) -> bool {
+        self.host_muted(&tracked.host)
+            || self.muted_panes.iter().any(|muted| muted.same_pane(tracked))
+    }
+
+    pub fn ensure_attention_len(&mut self) {
+        if self.attention.len() != self.panes.len() {
+            self.attention = vec![AttentionState::None; self.panes.len()];
+        }
+        if self.attention_since.len() != self.panes.len() {
+            self.attention_since = vec![None; self.panes.len()];
+        }
+    }
+
+    pub fn set_attention(&mut self, index: usize, attention: AttentionState) {
+        self.ensure_attention_len();
+        if let Some(flag) = self.attention.get_mut(index)
+            && *flag != attention
+        {
+            *flag = attention;
+            self.attention_since[index] = (attention != AttentionState::None).then(Instant::now);
+        }
+    }
+
+    pub fn mark_done_attention(&mut self, indices: &[usize]) {
+        for &index in indices {
+            let attention = if self.input_prompt(index).is_some() {
+                AttentionState::Input
+            } else {
+                AttentionState::Done
+            };
+            self.set_attention(index, attention);
+        }
+    }
+
+    pub fn escalates(&self, index: usize) -> bool {
+        let escalation = &self.config.notifications.escalation;
+        let kind = match self.attention.get(index) {
+            Some(AttentionState::Manual) => "attention",
+            Some(AttentionState::Done) => "done",
+            Some(AttentionState::Input) => "needs-input",
+            Some(AttentionState::None) | None => return false,
+        };
+        let selected = escalation.events.is_empty()
+            || escalation
+                .events
+                .iter()
+                .any(|event| event.trim().replace('_', "-") == kind);
+        selected
+            && self
+                .panes
+                .get(index)
+                .is_some_and(|pane| !self.pane_muted(&pane.tracked))
+    }
+
+    pub fn escalation(&self, index: usize) -> Option<Escalation> {
+        if !self.escalates(index) {
+            return None;
+        }
+        let escalation = &self.config.notifications.escalation;
+        let elapsed = self.attention_since.get(index).copied().flatten()?.elapsed();
+        if let Some(after) = escalation.renotify_after()
+            && elapsed < after
+        {
+            return Some(Escalation::Renotify(after - elapsed));
+        }
+        let after = escalation.escalate_after()?;
+        if elapsed < after {
+            Some(Escalation::Escalate(after - elapsed))
+        } else {
+            Some(Escalation::Escalated)
+        }
+    }
+
+    pub fn input_prompt(&self, index: usize) -> Option<String> {
+        if self.input_patterns.is_empty() {
+            return None;
+        }
+        let capture = self.panes.get(index)?.last_capture.as_ref()?;
+        let lines = plain_lines(&capture.lines);
+        let line = lines.iter().rev().find(|line| !line.trim().is_empty())?;
+        self.input_patterns
+            .iter()
+            .any(|pattern| pattern.is_match(line))
+            .then(|| line.trim().to_string())
+    }
+
+    pub fn clear_done_attention(&mut self, indices: &[usize]) {
+        self.ensure_attention_len();
+        for &index in indices {
+            let just_finished = self.panes.get(index).is_some_and(|pane| {
+                pane.finished
+                    .as_ref()
+                    .is_some_and(|finished| pane.last_change.is_some_and(|at| at <= finished.at))
+            });
+            if just_finished {
+                continue;
+            }
+            if self
+                .attention
+                .get(index)
+                .is_some_and(|flag| matches!(flag, AttentionState::Done | AttentionState::Input))
+            {
+                self.set_attention(index, AttentionState::None);
+            }
+        }
+    }
+
     pub fn is_active(&self, index: usize) -> bool {
         let active_for = Duration::from_secs(2);
         self.panes
@@ -221,6 +624,14 @@ impl AppState {
     }
 }
 
+// Invalid patterns are reported by validate when the config is loaded.
+fn compile_patterns(patterns: &[String]) -> Vec<Regex> {
+    patterns
+        .iter()
+        .filter_map(|pattern| Regex::new(pattern).ok())
+        .collect()
+}
+
 pub fn hash_capture(capture: &PaneCapture) -> u64 {
     let mut hash = 0xcbf29ce484222325u64;
     let prime = 0x100000001b3u64;
@@ -238,3 +649,66 @@ pub fn hash_capture(capture: &PaneCapture) -> u64 {
     }
     hash
 }
+
+pub fn strip_ansi(line: &str) -> String {
+    let mut out = String::with_capacity(line.len());
+    let mut chars = line.chars().peekable();
+    while let Some(ch) = chars.next() {
+        if ch == '\u{1b}' {
+            match chars.next() {
+                Some('[') => {
+                    for next in chars.by_ref() {
+                        if ('\u{40}'..='\u{7e}').contains(&next) {
+                            break;
+                        }
+                    }
+                }
+                Some(']') | Some('P') | Some('_') => {
+                    while let Some(next) = chars.next() {
+                        if next == '\u{7}' {
+                            break;
+                        }
+                        if next == '\u{1b}' && chars.peek() == Some(&'\\') {
+                            chars.next();
+                            break;
+                        }
+                    }
+                }
+                _ => {}
+            }
+            continue;
+        }
+        if ch.is_control() && ch != '\t' {
+            continue;
+        }
+        out.push(ch);
+    }
+    out
+}
+
+pub fn plain_lines(lines: &[String]) -> Vec<String> {
+    let mut plain: Vec<String> = lines
+        .iter()
+        .map(|line| strip_ansi(line).trim_end().to_string())
+        .collect();
+    while plain.last().is_some_and(|line| line.is_empty()) {
+        plain.pop();
+    }
+    plain
+}
+
+pub fn new_lines<'a>(previous: &[String], current: &'a [String]) -> &'a [String] {
+    appended(previous, current).unwrap_or(current)
+}
+
+// None when the captures share no lines, e.g. after a clear or more output
+// than fits on the screen between two polls.
+pub fn appended<'a>(previous: &[String], current: &'a [String]) -> Option<&'a [String]> {
+    let max = previous.len().min(current.len());
+    for overlap in (1..=max).rev() {
+        if previous[previous.len() - overlap..] == current[..overlap] {
+            return Some(&current[overlap..]);
+        }
+    }
+    None
+}
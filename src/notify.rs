@@ -0,0 +1,922 @@
+use crate::config::{Config, NotificationRoute, QuietHours, TrackedPane};
+use crate::dbus::{DesktopNotifier, Urgency};
+use crate::events::{FleetEvent, HostStatus};
+use crate::hooks::{self, HookRun, Hooks};
+use crate::model::{self, AppState, AttentionState, PaneState};
+use crate::osc::TerminalNotifier;
+use crate::ui;
+use crate::webhook::Webhooks;
+use anyhow::{anyhow, Context, Result};
+use chrono::{Datelike, Local, NaiveTime, Timelike, Weekday};
+use regex::Regex;
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, Semaphore};
+
+const ERROR_MATCH_COOLDOWN: Duration = Duration::from_secs(30);
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NotifyKind {
+    Done,
+    Attention,
+    NeedsInput,
+    ErrorMatch,
+    HostDown,
+    HostUp,
+}
+
+impl NotifyKind {
+    pub const ALL: [NotifyKind; 6] = [
+        NotifyKind::Done,
+        NotifyKind::Attention,
+        NotifyKind::NeedsInput,
+        NotifyKind::ErrorMatch,
+        NotifyKind::HostDown,
+        NotifyKind::HostUp,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            NotifyKind::Done => "done",
+            NotifyKind::Attention => "attention",
+            NotifyKind::NeedsInput => "needs-input",
+            NotifyKind::ErrorMatch => "error-match",
+            NotifyKind::HostDown => "host-down",
+            NotifyKind::HostUp => "host-up",
+        }
+    }
+
+    pub fn parse(name: &str) -> Result<Self> {
+        let normalized = name.trim().replace('_', "-");
+        Self::ALL
+            .into_iter()
+            .find(|kind| kind.name() == normalized)
+            .ok_or_else(|| {
+                anyhow!(
+                    "Unknown event {name:?} (expected one of: {})",
+                    Self::ALL.map(NotifyKind::name).join(", ")
+                )
+            })
+    }
+
+    pub fn parse_list(names: &[String]) -> Result<Vec<Self>> {
+        if names.is_empty() {
+            return Ok(Self::ALL.to_vec());
+        }
+        names.iter().map(|name| Self::parse(name)).collect()
+    }
+
+    fn title(self) -> &'static str {
+        match self {
+            NotifyKind::Done => "Pane done",
+            NotifyKind::Attention => "Pane needs attention",
+            NotifyKind::NeedsInput => "Pane waiting for input",
+            NotifyKind::ErrorMatch => "Error in pane",
+            NotifyKind::HostDown => "Host down",
+            NotifyKind::HostUp => "Host recovered",
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Notification {
+    pub kind: NotifyKind,
+    pub title: String,
+    pub body: String,
+    pub urgency: Urgency,
+    pub host: String,
+    pub pane: Option<TrackedPane>,
+    pub target: Option<String>,
+    pub ts: String,
+}
+
+impl Notification {
+    pub fn text(&self) -> String {
+        let body: Vec<&str> = self.body.lines().collect();
+        format!("{}: {}", self.title, body.join(" | "))
+    }
+}
+
+pub trait Notifier {
+    fn notify(&mut self, batch: &[&Notification]);
+
+    fn take_errors(&mut self) -> Vec<String> {
+        Vec::new()
+    }
+}
+
+struct BellNotifier;
+
+impl Notifier for BellNotifier {
+    fn notify(&mut self, batch: &[&Notification]) {
+        if !batch.is_empty() {
+            let _ = ui::bell();
+        }
+    }
+}
+
+struct MacosNotifier {
+    only_when_inactive: bool,
+    ignore_apps: Vec<String>,
+    sender: Option<String>,
+}
+
+impl Notifier for MacosNotifier {
+    fn notify(&mut self, batch: &[&Notification]) {
+        let Some(first) = batch.first() else {
+            return;
+        };
+        if self.only_when_inactive
+            && let Ok(Some(app)) = ui::macos_frontmost_app()
+            && self
+                .ignore_apps
+                .iter()
+                .any(|name| name.eq_ignore_ascii_case(&app))
+        {
+            return;
+        }
+        let max = 3usize;
+        let mut lines: Vec<&str> = batch
+            .iter()
+            .take(max)
+            .map(|notification| notification.body.as_str())
+            .collect();
+        let extra = batch.len().saturating_sub(max);
+        let more = format!("+{extra} more");
+        if extra > 0 {
+            lines.push(&more);
+        }
+        let message = if batch.len() == 1 {
+            format!("{}:\n{}", first.title, lines.join("\n"))
+        } else {
+            format!("{} ({})\n{}", first.title, batch.len(), lines.join("\n"))
+        };
+        let _ = ui::notify_macos("FleetMux", &message, self.sender.as_deref());
+    }
+}
+
+impl Notifier for DesktopNotifier {
+    fn notify(&mut self, batch: &[&Notification]) {
+        for notification in batch {
+            DesktopNotifier::notify(
+                self,
+                &notification.host,
+                notification.pane.as_ref(),
+                &format!("FleetMux: {}", notification.title),
+                &notification.body,
+                notification.urgency,
+            );
+        }
+    }
+
+    fn take_errors(&mut self) -> Vec<String> {
+        DesktopNotifier::take_errors(self)
+    }
+}
+
+impl Notifier for TerminalNotifier {
+    fn notify(&mut self, batch: &[&Notification]) {
+        for notification in batch {
+            let _ = self.send(&format!("FleetMux: {}", notification.title), &notification.body);
+        }
+    }
+}
+
+struct CommandNotifier {
+    command: String,
+    running: Arc<Semaphore>,
+    errors_tx: mpsc::UnboundedSender<String>,
+    errors_rx: mpsc::UnboundedReceiver<String>,
+}
+
+impl Notifier for CommandNotifier {
+    fn notify(&mut self, batch: &[&Notification]) {
+        for notification in batch {
+            let mut env = hooks::pane_env(
+                notification.kind.name(),
+                &notification.host,
+                notification.pane.as_ref(),
+            );
+            env.push(("FLEETMUX_TITLE".to_string(), notification.title.clone()));
+            env.push(("FLEETMUX_MESSAGE".to_string(), notification.body.clone()));
+            hooks::spawn(
+                HookRun {
+                    name: "notifications.command".to_string(),
+                    command: self.command.clone(),
+                    env,
+                    stdin: String::new(),
+                    timeout: COMMAND_TIMEOUT,
+                },
+                &self.running,
+                &self.errors_tx,
+            );
+        }
+    }
+
+    fn take_errors(&mut self) -> Vec<String> {
+        let mut errors = Vec::new();
+        while let Ok(error) = self.errors_rx.try_recv() {
+            errors.push(error);
+        }
+        errors
+    }
+}
+
+impl Notifier for Webhooks {
+    fn notify(&mut self, batch: &[&Notification]) {
+        for notification in batch {
+            self.dispatch(notification);
+        }
+    }
+
+    fn take_errors(&mut self) -> Vec<String> {
+        Webhooks::take_errors(self)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Filter {
+    pub hosts: Vec<String>,
+    pub tags: Vec<String>,
+    pub panes: Vec<String>,
+}
+
+impl Filter {
+    pub fn matches(
+        &self,
+        host: &str,
+        pane: Option<&TrackedPane>,
+        host_tags: &HashMap<String, Vec<String>>,
+    ) -> bool {
+        if !self.hosts.is_empty() && !self.hosts.iter().any(|name| name == host) {
+            return false;
+        }
+        if !self.tags.is_empty() {
+            let tags = host_tags.get(host);
+            if !tags.is_some_and(|tags| tags.iter().any(|tag| self.tags.contains(tag))) {
+                return false;
+            }
+        }
+        if !self.panes.is_empty() {
+            let Some(pane) = pane else {
+                return false;
+            };
+            let matched = self.panes.iter().any(|filter| {
+                filter == &pane.pane_id || pane.label.as_deref() == Some(filter.as_str())
+            });
+            if !matched {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+struct Route {
+    kinds: Vec<NotifyKind>,
+    backends: Vec<usize>,
+    filter: Filter,
+}
+
+impl Route {
+    fn matches(&self, notification: &Notification, host_tags: &HashMap<String, Vec<String>>) -> bool {
+        self.kinds.contains(&notification.kind)
+            && self
+                .filter
+                .matches(&notification.host, notification.pane.as_ref(), host_tags)
+    }
+}
+
+struct QuietWindow {
+    days: Vec<Weekday>,
+    start: u32,
+    end: u32,
+    kinds: Vec<NotifyKind>,
+}
+
+impl QuietWindow {
+    fn parse(config: &QuietHours) -> Result<Self> {
+        let mut days = Vec::new();
+        for day in &config.days {
+            match day.trim().to_ascii_lowercase().as_str() {
+                "weekdays" => days.extend([
+                    Weekday::Mon,
+                    Weekday::Tue,
+                    Weekday::Wed,
+                    Weekday::Thu,
+                    Weekday::Fri,
+                ]),
+                "weekends" => days.extend([Weekday::Sat, Weekday::Sun]),
+                name => days.push(name.parse::<Weekday>().map_err(|_| {
+                    anyhow!("Unknown day {day:?} (expected mon..sun, weekdays or weekends)")
+                })?),
+            }
+        }
+        Ok(Self {
+            days,
+            start: parse_minutes(&config.start)?,
+            end: parse_minutes(&config.end)?,
+            kinds: NotifyKind::parse_list(&config.events)?,
+        })
+    }
+
+    fn on_day(&self, day: Weekday) -> bool {
+        self.days.is_empty() || self.days.contains(&day)
+    }
+
+    fn active(&self, now: chrono::DateTime<Local>, kind: NotifyKind) -> bool {
+        if !self.kinds.contains(&kind) {
+            return false;
+        }
+        let minutes = now.hour() * 60 + now.minute();
+        let today = now.weekday();
+        if self.start <= self.end {
+            self.on_day(today) && minutes >= self.start && minutes < self.end
+        } else {
+            (self.on_day(today) && minutes >= self.start)
+                || (self.on_day(today.pred()) && minutes < self.end)
+        }
+    }
+}
+
+fn parse_minutes(value: &str) -> Result<u32> {
+    let time = NaiveTime::parse_from_str(value.trim(), "%H:%M")
+        .map_err(|_| anyhow!("Invalid time {value:?} (expected HH:MM)"))?;
+    Ok(time.hour() * 60 + time.minute())
+}
+
+pub struct Router {
+    backends: Vec<Box<dyn Notifier>>,
+    routes: Vec<Route>,
+    quiet_hours: Vec<QuietWindow>,
+    error_patterns: Vec<Regex>,
+    done_urgency: Urgency,
+    host_tags: HashMap<String, Vec<String>>,
+    hosts_down: HashSet<String>,
+    settled: HashMap<String, Vec<String>>,
+    last_error_match: HashMap<String, Instant>,
+    pending: Vec<Notification>,
+    hooks: Hooks,
+    actions: Option<mpsc::UnboundedReceiver<TrackedPane>>,
+    escalation_backends: Vec<usize>,
+    escalation_sent: HashMap<usize, (Instant, u8)>,
+}
+
+impl Router {
+    pub fn new(config: &Config, headless: bool) -> Result<Self> {
+        let done_urgency = Urgency::parse(&config.ui.linux_notify_urgency).ok_or_else(|| {
+            anyhow!(
+                "Invalid ui.linux_notify_urgency {:?} (expected low, normal or critical)",
+                config.ui.linux_notify_urgency
+            )
+        })?;
+        let error_patterns = config
+            .notifications
+            .error_patterns
+            .iter()
+            .map(|pattern| {
+                Regex::new(pattern).with_context(|| format!("Invalid error pattern {pattern:?}"))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        for pattern in &config.notifications.input_patterns {
+            Regex::new(pattern).with_context(|| format!("Invalid input pattern {pattern:?}"))?;
+        }
+        let quiet_hours = config
+            .notifications
+            .quiet_hours
+            .iter()
+            .map(|window| {
+                QuietWindow::parse(window).with_context(|| {
+                    format!("Invalid quiet hours {}-{}", window.start, window.end)
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let host_tags = config
+            .hosts
+            .iter()
+            .map(|host| (host.name.clone(), host.tags.clone().unwrap_or_default()))
+            .collect();
+
+        let mut router = Self {
+            backends: Vec::new(),
+            routes: Vec::new(),
+            quiet_hours,
+            error_patterns,
+            done_urgency,
+            host_tags,
+            hosts_down: HashSet::new(),
+            settled: HashMap::new(),
+            last_error_match: HashMap::new(),
+            pending: Vec::new(),
+            hooks: Hooks::new(&config.hooks)?,
+            actions: None,
+            escalation_backends: Vec::new(),
+            escalation_sent: HashMap::new(),
+        };
+        let routes = if config.notifications.routes.is_empty() {
+            default_routes(config)
+        } else {
+            config.notifications.routes.clone()
+        };
+        let mut backend_index: HashMap<String, usize> = HashMap::new();
+        for (position, route) in routes.iter().enumerate() {
+            let kinds = NotifyKind::parse_list(&route.events)
+                .with_context(|| format!("Invalid notification route #{}", position + 1))?;
+            let backends = router
+                .resolve_backends(config, &route.backends, headless, &mut backend_index)
+                .with_context(|| format!("Invalid notification route #{}", position + 1))?;
+            router.routes.push(Route {
+                kinds,
+                backends,
+                filter: Filter {
+                    hosts: route.hosts.clone(),
+                    tags: route.tags.clone(),
+                    panes: route.panes.clone(),
+                },
+            });
+        }
+        let escalation = &config.notifications.escalation;
+        if escalation.escalate_after_min > 0 && escalation.backends.is_empty() {
+            return Err(anyhow!(
+                "Invalid notifications.escalation: backends are required with escalate_after_min"
+            ));
+        }
+        for event in &escalation.events {
+            let kind = NotifyKind::parse(event).context("Invalid notifications.escalation")?;
+            if !matches!(
+                kind,
+                NotifyKind::Done | NotifyKind::Attention | NotifyKind::NeedsInput
+            ) {
+                return Err(anyhow!(
+                    "Invalid notifications.escalation: {event:?} cannot be acknowledged (expected done, attention or needs-input)"
+                ));
+            }
+        }
+        router.escalation_backends = router
+            .resolve_backends(config, &escalation.backends, headless, &mut backend_index)
+            .context("Invalid notifications.escalation")?;
+        Ok(router)
+    }
+
+    fn resolve_backends(
+        &mut self,
+        config: &Config,
+        names: &[String],
+        headless: bool,
+        backend_index: &mut HashMap<String, usize>,
+    ) -> Result<Vec<usize>> {
+        let mut backends = Vec::new();
+        for name in names {
+            let name = name.trim().to_string();
+            if let Some(index) = backend_index.get(&name) {
+                backends.push(*index);
+                continue;
+            }
+            if let Some(backend) = self.build_backend(config, &name, headless)? {
+                self.backends.push(backend);
+                backend_index.insert(name, self.backends.len() - 1);
+                backends.push(self.backends.len() - 1);
+            }
+        }
+        Ok(backends)
+    }
+
+    fn build_backend(
+        &mut self,
+        config: &Config,
+        name: &str,
+        headless: bool,
+    ) -> Result<Option<Box<dyn Notifier>>> {
+        let local = !headless;
+        let backend: Box<dyn Notifier> = match name {
+            "bell" if local => Box::new(BellNotifier),
+            "macos" if local => Box::new(MacosNotifier {
+                only_when_inactive: config.ui.macos_notify_only_when_inactive,
+                ignore_apps: config.ui.macos_notify_ignore_apps.clone(),
+                sender: config.ui.macos_notify_sender.clone(),
+            }),
+            "linux" if local => {
+                let mut desktop = DesktopNotifier::new(&config.ui);
+                self.actions = desktop.take_actions();
+                Box::new(desktop)
+            }
+            "terminal" if local => Box::new(TerminalNotifier::new(&config.ui)?),
+            "bell" | "macos" | "linux" | "terminal" => return Ok(None),
+            "command" => {
+                let command = config
+                    .notifications
+                    .command
+                    .clone()
+                    .filter(|command| !command.trim().is_empty())
+                    .ok_or_else(|| anyhow!("Backend \"command\" needs notifications.command"))?;
+                let (errors_tx, errors_rx) = mpsc::unbounded_channel();
+                Box::new(CommandNotifier {
+                    command,
+                    running: Arc::new(Semaphore::new(4)),
+                    errors_tx,
+                    errors_rx,
+                })
+            }
+            "webhook" => Box::new(Webhooks::new(&config.webhooks)?),
+            _ => {
+                let Some(webhook) = name.strip_prefix("webhook:") else {
+                    return Err(anyhow!(
+                        "Unknown backend {name:?} (expected bell, macos, linux, terminal, command, webhook or webhook:<name>)"
+                    ));
+                };
+                let selected: Vec<_> = config
+                    .webhooks
+                    .iter()
+                    .filter(|hook| hook.name.as_deref() == Some(webhook))
+                    .cloned()
+                    .collect();
+                if selected.is_empty() {
+                    return Err(anyhow!("No webhook named {webhook:?}"));
+                }
+                Box::new(Webhooks::new(&selected)?)
+            }
+        };
+        Ok(Some(backend))
+    }
+
+    pub async fn next_action(&mut self) -> Option<TrackedPane> {
+        match &mut self.actions {
+            Some(actions) => actions.recv().await,
+            None => std::future::pending().await,
+        }
+    }
+
+    pub fn observe_output(&mut self, pane: &PaneState) {
+        if self.error_patterns.is_empty() {
+            return;
+        }
+        let Some(capture) = &pane.last_capture else {
+            return;
+        };
+        let stem = pane.tracked.file_stem();
+        let mut lines = model::plain_lines(&capture.lines);
+        lines.pop();
+        let previous = self.settled.get(&stem).map(Vec::as_slice).unwrap_or(&[]);
+        let first_seen = !self.settled.contains_key(&stem);
+        let matched = model::new_lines(previous, &lines)
+            .iter()
+            .find(|line| self.error_patterns.iter().any(|pattern| pattern.is_match(line)))
+            .cloned();
+        self.settled.insert(stem.clone(), lines);
+        let Some(line) = matched else {
+            return;
+        };
+        if first_seen {
+            return;
+        }
+        let now = Instant::now();
+        if self
+            .last_error_match
+            .get(&stem)
+            .is_some_and(|at| now.duration_since(*at) < ERROR_MATCH_COOLDOWN)
+        {
+            return;
+        }
+        self.last_error_match.insert(stem, now);
+        self.pending.push(Notification {
+            kind: NotifyKind::ErrorMatch,
+            title: NotifyKind::ErrorMatch.title().to_string(),
+            body: format!("{}\n{}", pane_line(pane), line.trim()),
+            urgency: Urgency::Critical,
+            host: pane.tracked.host.clone(),
+            pane: Some(pane.tracked.clone()),
+            target: None,
+            ts: timestamp(),
+        });
+    }
+
+    pub fn handle(&mut self, state: &AppState, events: &[FleetEvent]) -> Vec<String> {
+        let mut notifications = std::mem::take(&mut self.pending);
+        let finished: HashSet<usize> = events
+            .iter()
+            .filter_map(|event| match event {
+                FleetEvent::CommandFinished { pane, .. } => Some(pane.index),
+                _ => None,
+            })
+            .collect();
+        for event in events {
+            if let FleetEvent::Attention {
+                pane,
+                attention: AttentionState::Done,
+                ..
+            } = event
+                && finished.contains(&pane.index)
+            {
+                continue;
+            }
+            if let Some(notification) = self.notification_for(state, event) {
+                notifications.push(notification);
+            }
+        }
+        self.hooks
+            .run(state, events, &notifications, &self.host_tags);
+        let snoozed = state
+            .notify_snooze_until
+            .is_some_and(|until| Instant::now() < until);
+        if snoozed {
+            notifications.retain(|notification| notification.kind != NotifyKind::Done);
+        }
+        let now = Local::now();
+        notifications.retain(|notification| self.allowed(state, notification, now));
+        let (renotify, escalated) = self.due_escalations(state, now, snoozed);
+        notifications.extend(renotify);
+
+        let mut batches: Vec<Vec<&Notification>> = vec![Vec::new(); self.backends.len()];
+        for notification in &notifications {
+            let mut targets = HashSet::new();
+            for route in &self.routes {
+                if route.matches(notification, &self.host_tags) {
+                    targets.extend(route.backends.iter().copied());
+                }
+            }
+            for index in targets {
+                batches[index].push(notification);
+            }
+        }
+        for notification in &escalated {
+            for &index in &self.escalation_backends {
+                batches[index].push(notification);
+            }
+        }
+        let mut errors = self.hooks.take_errors();
+        for (backend, batch) in self.backends.iter_mut().zip(batches) {
+            if !batch.is_empty() {
+                backend.notify(&batch);
+            }
+            errors.extend(backend.take_errors());
+        }
+        errors
+    }
+
+    fn attention_notification(
+        &self,
+        state: &AppState,
+        index: usize,
+        attention: AttentionState,
+        ts: &str,
+    ) -> Option<Notification> {
+        let (kind, urgency) = match attention {
+            AttentionState::Done => (NotifyKind::Done, self.done_urgency),
+            AttentionState::Input => (NotifyKind::NeedsInput, Urgency::Critical),
+            AttentionState::Manual => (NotifyKind::Attention, Urgency::Normal),
+            AttentionState::None => return None,
+        };
+        let pane_state = state.panes.get(index)?;
+        let mut body = pane_line(pane_state);
+        if attention == AttentionState::Input
+            && let Some(prompt) = state.input_prompt(index)
+        {
+            body.push('\n');
+            body.push_str(&prompt);
+        }
+        Some(Notification {
+            kind,
+            title: kind.title().to_string(),
+            body,
+            urgency,
+            host: pane_state.tracked.host.clone(),
+            pane: Some(pane_state.tracked.clone()),
+            target: None,
+            ts: ts.to_string(),
+        })
+    }
+
+    fn allowed(
+        &self,
+        state: &AppState,
+        notification: &Notification,
+        now: chrono::DateTime<Local>,
+    ) -> bool {
+        let muted = match &notification.pane {
+            Some(pane) => state.pane_muted(pane),
+            None => state.host_muted(&notification.host),
+        };
+        !muted
+            && !self
+                .quiet_hours
+                .iter()
+                .any(|window| window.active(now, notification.kind))
+    }
+
+    // Filters before recording a stage as sent, so anything held back by a
+    // mute, snooze or quiet hours is tried again on a later tick.
+    fn due_escalations(
+        &mut self,
+        state: &AppState,
+        now: chrono::DateTime<Local>,
+        snoozed: bool,
+    ) -> (Vec<Notification>, Vec<Notification>) {
+        let escalation = &state.config.notifications.escalation;
+        let renotify_after = escalation.renotify_after();
+        let escalate_after = escalation.escalate_after();
+        let mut renotify = Vec::new();
+        let mut escalate = Vec::new();
+        if renotify_after.is_none() && escalate_after.is_none() {
+            return (renotify, escalate);
+        }
+        self.escalation_sent.retain(|index, (since, _)| {
+            state.attention_since.get(*index).copied().flatten() == Some(*since)
+        });
+        let ts = timestamp();
+        for index in 0..state.panes.len() {
+            let Some(since) = state.attention_since.get(index).copied().flatten() else {
+                continue;
+            };
+            if !state.escalates(index) {
+                continue;
+            }
+            let elapsed = since.elapsed();
+            let stage = if escalate_after.is_some_and(|after| elapsed >= after) {
+                2
+            } else if renotify_after.is_some_and(|after| elapsed >= after) {
+                1
+            } else {
+                0
+            };
+            let sent = self.escalation_sent.get(&index).map_or(0, |(_, sent)| *sent);
+            if stage <= sent {
+                continue;
+            }
+            let Some(mut notification) =
+                self.attention_notification(state, index, state.attention[index], &ts)
+            else {
+                continue;
+            };
+            let snoozed = snoozed && stage == 1 && notification.kind == NotifyKind::Done;
+            if snoozed || !self.allowed(state, &notification, now) {
+                continue;
+            }
+            self.escalation_sent.insert(index, (since, stage));
+            let minutes = elapsed.as_secs() / 60;
+            if stage == 2 {
+                notification.title = format!("Unacknowledged for {minutes}m: {}", notification.title);
+                notification.urgency = Urgency::Critical;
+                escalate.push(notification);
+            } else {
+                notification.title = format!("Still waiting after {minutes}m: {}", notification.title);
+                renotify.push(notification);
+            }
+        }
+        (renotify, escalate)
+    }
+
+    fn notification_for(&mut self, state: &AppState, event: &FleetEvent) -> Option<Notification> {
+        match event {
+            FleetEvent::Attention {
+                ts,
+                pane,
+                attention,
+                ..
+            } => self.attention_notification(state, pane.index, *attention, ts),
+            FleetEvent::CommandFinished {
+                ts,
+                pane,
+                exit_status,
+                ..
+            } => {
+                let pane_state = state.panes.get(pane.index)?;
+                let finished = pane_state.finished.as_ref()?;
+                let urgency = match exit_status {
+                    Some(code) if *code != 0 => Urgency::Critical,
+                    _ => self.done_urgency,
+                };
+                Some(Notification {
+                    kind: NotifyKind::Done,
+                    title: NotifyKind::Done.title().to_string(),
+                    body: format!("{}\n{}", finished.describe(), pane_line(pane_state)),
+                    urgency,
+                    host: pane.host.clone(),
+                    pane: Some(pane_state.tracked.clone()),
+                    target: None,
+                    ts: ts.clone(),
+                })
+            }
+            FleetEvent::Host {
+                ts,
+                host,
+                status,
+                target,
+                ..
+            } => {
+                let (kind, urgency, body) = match status {
+                    HostStatus::Down => {
+                        if !self.hosts_down.insert(host.clone()) {
+                            return None;
+                        }
+                        (NotifyKind::HostDown, Urgency::Critical, host.clone())
+                    }
+                    HostStatus::Up => {
+                        if !self.hosts_down.remove(host) {
+                            return None;
+                        }
+                        let body = match target {
+                            Some(target) => format!("{host} via {target}"),
+                            None => host.clone(),
+                        };
+                        (NotifyKind::HostUp, Urgency::Low, body)
+                    }
+                };
+                Some(Notification {
+                    kind,
+                    title: kind.title().to_string(),
+                    body,
+                    urgency,
+                    host: host.clone(),
+                    pane: None,
+                    target: target.clone(),
+                    ts: ts.clone(),
+                })
+            }
+            _ => None,
+        }
+    }
+}
+
+fn default_routes(config: &Config) -> Vec<NotificationRoute> {
+    let mut routes = Vec::new();
+    let mut local = Vec::new();
+    if config.ui.bell_on_stop {
+        local.push("bell".to_string());
+    }
+    if config.ui.macos_notification_on_stop {
+        local.push("macos".to_string());
+    }
+    if config.ui.linux_notification_on_stop {
+        local.push("linux".to_string());
+    }
+    if config.ui.terminal_notification_on_stop {
+        local.push("terminal".to_string());
+    }
+    if !local.is_empty() {
+        routes.push(NotificationRoute {
+            events: vec![
+                NotifyKind::Done.name().to_string(),
+                NotifyKind::NeedsInput.name().to_string(),
+                NotifyKind::ErrorMatch.name().to_string(),
+            ],
+            backends: local,
+            ..NotificationRoute::default()
+        });
+    }
+    if config
+        .notifications
+        .command
+        .as_deref()
+        .is_some_and(|command| !command.trim().is_empty())
+    {
+        routes.push(NotificationRoute {
+            backends: vec!["command".to_string()],
+            ..NotificationRoute::default()
+        });
+    }
+    if !config.webhooks.is_empty() {
+        routes.push(NotificationRoute {
+            backends: vec!["webhook".to_string()],
+            ..NotificationRoute::default()
+        });
+    }
+    routes
+}
+
+pub fn pane_line(pane: &PaneState) -> String {
+    let label = pane
+        .tracked
+        .label
+        .as_deref()
+        .filter(|value| !value.is_empty())
+        .map(|value| value.to_string())
+        .or_else(|| {
+            pane.last_capture.as_ref().and_then(|capture| {
+                if !capture.title.is_empty() {
+                    Some(capture.title.clone())
+                } else if !capture.command.is_empty() {
+                    Some(capture.command.clone())
+                } else {
+                    None
+                }
+            })
+        })
+        .unwrap_or_else(|| format_pane_id(&pane.tracked.pane_id));
+    format!(
+        "{} {}:{} {}",
+        pane.tracked.host, pane.tracked.session, pane.tracked.window, label
+    )
+}
+
+fn format_pane_id(pane_id: &str) -> String {
+    pane_id
+        .strip_prefix('%')
+        .map(|id| format!("pane {id}"))
+        .unwrap_or_else(|| format!("pane {pane_id}"))
+}
+
+fn timestamp() -> String {
+    chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false)
+}
@@ -0,0 +1,103 @@
+use crate::config::UiConfig;
+use anyhow::{anyhow, Result};
+use std::io::{self, Write};
+
+const ESC: char = '\x1b';
+const BEL: char = '\x07';
+const ST: &str = "\x1b\\";
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum OscFormat {
+    Osc9,
+    Osc777,
+    Osc99,
+}
+
+impl OscFormat {
+    pub fn parse(value: &str) -> Option<Self> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "osc9" | "9" => Some(OscFormat::Osc9),
+            "osc777" | "777" => Some(OscFormat::Osc777),
+            "osc99" | "99" | "kitty" => Some(OscFormat::Osc99),
+            _ => None,
+        }
+    }
+}
+
+pub struct TerminalNotifier {
+    format: OscFormat,
+    passthrough: bool,
+    next_id: u64,
+}
+
+impl TerminalNotifier {
+    pub fn new(config: &UiConfig) -> Result<Self> {
+        let format = OscFormat::parse(&config.terminal_notify_format).ok_or_else(|| {
+            anyhow!(
+                "Invalid ui.terminal_notify_format {:?} (expected osc9, osc777 or osc99)",
+                config.terminal_notify_format
+            )
+        })?;
+        let passthrough = match config.terminal_notify_passthrough.trim() {
+            "auto" => std::env::var("TMUX").is_ok_and(|value| !value.is_empty()),
+            "always" => true,
+            "never" => false,
+            other => {
+                return Err(anyhow!(
+                    "Invalid ui.terminal_notify_passthrough {other:?} (expected auto, always or never)"
+                ));
+            }
+        };
+        Ok(Self {
+            format,
+            passthrough,
+            next_id: 1,
+        })
+    }
+
+    pub fn send(&mut self, title: &str, body: &str) -> Result<()> {
+        let id = self.next_id;
+        self.next_id += 1;
+        let mut sequence = sequence(self.format, &clean(title), &clean(body), id);
+        if self.passthrough {
+            sequence = tmux_passthrough(&sequence);
+        }
+        let mut stdout = io::stdout();
+        stdout.write_all(sequence.as_bytes())?;
+        stdout.flush()?;
+        Ok(())
+    }
+}
+
+pub fn send_test(config: &UiConfig) -> Result<()> {
+    TerminalNotifier::new(config)?.send("FleetMux", "Test notification from fleetmux")
+}
+
+fn sequence(format: OscFormat, title: &str, body: &str, id: u64) -> String {
+    match format {
+        OscFormat::Osc9 => format!("{ESC}]9;{title}: {body}{BEL}"),
+        OscFormat::Osc777 => {
+            format!("{ESC}]777;notify;{};{body}{BEL}", title.replace(';', ","))
+        }
+        OscFormat::Osc99 => format!(
+            "{ESC}]99;i=fleetmux-{id}:d=0;{title}{ST}{ESC}]99;i=fleetmux-{id}:p=body;{body}{ST}"
+        ),
+    }
+}
+
+fn tmux_passthrough(sequence: &str) -> String {
+    format!("{ESC}Ptmux;{}{ST}", sequence.replace(ESC, "\x1b\x1b"))
+}
+
+fn clean(text: &str) -> String {
+    let lines: Vec<&str> = text
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty())
+        .collect();
+    lines
+        .join(" | ")
+        .chars()
+        .filter(|ch| !ch.is_control())
+        .collect()
+}
@@ -0,0 +1,177 @@
+use crate::config::LogConfig;
+use crate::model::{self, PaneState};
+use anyhow::{Context, Result};
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+const LOG_EXTENSION: &str = "log";
+
+struct LogFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    settled: Option<Vec<String>>,
+}
+
+pub struct PaneLogger {
+    config: LogConfig,
+    dir: PathBuf,
+    files: HashMap<String, LogFile>,
+}
+
+impl PaneLogger {
+    pub fn new(config: &LogConfig) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let dir = config.logs_dir()?;
+        fs::create_dir_all(&dir)
+            .with_context(|| format!("Unable to create logs directory: {}", dir.display()))?;
+        prune_expired(&dir, config.max_age_days);
+        Ok(Some(Self {
+            config: config.clone(),
+            dir,
+            files: HashMap::new(),
+        }))
+    }
+
+    pub fn append(&mut self, pane: &PaneState) -> Result<()> {
+        let Some(capture) = &pane.last_capture else {
+            return Ok(());
+        };
+        let stem = pane.tracked.file_stem();
+        let mut lines = model::plain_lines(&capture.lines);
+        // The last line may still be in progress (a prompt being typed, a progress bar),
+        // so it is only logged once another line appears below it.
+        lines.pop();
+
+        if !self.files.contains_key(&stem) {
+            let path = self.dir.join(format!("{stem}.{LOG_EXTENSION}"));
+            let mut log = open_log(&path)?;
+            let marker = format!(
+                "# fleetmux: logging {} {}:{} {}",
+                pane.tracked.host, pane.tracked.session, pane.tracked.window, pane.tracked.pane_id
+            );
+            write_lines(&mut log, &self.config, &[marker])?;
+            self.files.insert(stem.clone(), log);
+        }
+        let Some(log) = self.files.get_mut(&stem) else {
+            return Ok(());
+        };
+
+        // The first capture is what was already on screen, often logged by an
+        // earlier run, so it only seeds the diff.
+        let appeared = match &log.settled {
+            None => Vec::new(),
+            Some(settled) if settled.is_empty() => lines.clone(),
+            Some(settled) => match model::appended(settled, &lines) {
+                Some(appeared) => appeared.to_vec(),
+                None if lines.is_empty() => Vec::new(),
+                None => vec![
+                    "# fleetmux: output skipped, no overlap with the previous capture".to_string(),
+                ],
+            },
+        };
+        log.settled = Some(lines);
+        if appeared.is_empty() {
+            return Ok(());
+        }
+        if self.config.max_bytes > 0 && log.size >= self.config.max_bytes {
+            rotate(&log.path, self.config.keep)?;
+            prune_expired(&self.dir, self.config.max_age_days);
+            let settled = std::mem::take(&mut log.settled);
+            *log = open_log(&log.path)?;
+            log.settled = settled;
+        }
+        write_lines(log, &self.config, &appeared)
+    }
+}
+
+fn open_log(path: &Path) -> Result<LogFile> {
+    let file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Unable to open log file: {}", path.display()))?;
+    let size = file.metadata().map(|meta| meta.len()).unwrap_or(0);
+    Ok(LogFile {
+        path: path.to_path_buf(),
+        file,
+        size,
+        settled: None,
+    })
+}
+
+fn write_lines(log: &mut LogFile, config: &LogConfig, lines: &[String]) -> Result<()> {
+    let prefix = if config.timestamps {
+        chrono::Local::now().format("[%Y-%m-%d %H:%M:%S] ").to_string()
+    } else {
+        String::new()
+    };
+    let mut chunk = String::new();
+    for line in lines {
+        chunk.push_str(&prefix);
+        chunk.push_str(line);
+        chunk.push('\n');
+    }
+    log.file
+        .write_all(chunk.as_bytes())
+        .with_context(|| format!("Unable to write log file: {}", log.path.display()))?;
+    log.size += chunk.len() as u64;
+    Ok(())
+}
+
+fn rotated_path(path: &Path, generation: usize) -> PathBuf {
+    let mut name = path.as_os_str().to_os_string();
+    name.push(format!(".{generation}"));
+    PathBuf::from(name)
+}
+
+fn rotate(path: &Path, keep: usize) -> Result<()> {
+    if keep == 0 {
+        return fs::remove_file(path)
+            .with_context(|| format!("Unable to rotate log file: {}", path.display()));
+    }
+    let _ = fs::remove_file(rotated_path(path, keep));
+    for generation in (1..keep).rev() {
+        let from = rotated_path(path, generation);
+        if from.exists() {
+            fs::rename(&from, rotated_path(path, generation + 1))
+                .with_context(|| format!("Unable to rotate log file: {}", from.display()))?;
+        }
+    }
+    fs::rename(path, rotated_path(path, 1))
+        .with_context(|| format!("Unable to rotate log file: {}", path.display()))
+}
+
+fn prune_expired(dir: &Path, max_age_days: u64) {
+    if max_age_days == 0 {
+        return;
+    }
+    let max_age = Duration::from_secs(max_age_days * 24 * 60 * 60);
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_rotated = path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| name.contains(&format!(".{LOG_EXTENSION}.")));
+        if !is_rotated {
+            continue;
+        }
+        let expired = entry
+            .metadata()
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+            .is_some_and(|age| age > max_age);
+        if expired {
+            let _ = fs::remove_file(path);
+        }
+    }
+}
@@ -1,48 +1,138 @@
-use crate::config::Config;
+use crate::config::{Config, HostConfig, SshConfig, TrackedPane};
 use crate::model::{PaneStatus, PaneUpdate};
 use crate::ssh::HostResolver;
 use crate::tmux;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::sync::Arc;
 use std::time::{Duration, Instant};
 use tokio::sync::{broadcast, mpsc, Mutex};
 use tokio::task::JoinHandle;
 
-pub struct PollerHandle {
+#[derive(Clone, Debug, PartialEq)]
+struct PollerSpec {
+    tracked: TrackedPane,
+    host: Option<HostConfig>,
+    ssh: SshConfig,
+    refresh: Duration,
+    lines: usize,
+    join_lines: bool,
+    ansi: bool,
+}
+
+impl PollerSpec {
+    fn all(config: &Config) -> Vec<Self> {
+        config
+            .tracked
+            .iter()
+            .map(|tracked| {
+                let host = config.hosts.iter().find(|h| h.name == tracked.host);
+                Self {
+                    tracked: tracked.clone(),
+                    host: host.cloned(),
+                    ssh: host.map_or_else(
+                        || config.ssh.clone(),
+                        |host| config.ssh.for_host(host),
+                    ),
+                    refresh: config.ui.refresh(),
+                    lines: config.ui.lines,
+                    join_lines: config.ui.join_lines,
+                    ansi: config.ui.ansi(),
+                }
+            })
+            .collect()
+    }
+
+    fn same_poll(&self, other: &Self) -> bool {
+        let mut other = other.clone();
+        other.tracked.label = self.tracked.label.clone();
+        *self == other
+    }
+}
+
+struct Poller {
+    id: u64,
+    index: Arc<AtomicUsize>,
+    spec: PollerSpec,
     shutdown: broadcast::Sender<()>,
-    tasks: Vec<JoinHandle<()>>,
+    task: JoinHandle<()>,
+}
+
+pub struct PollerHandle {
+    resolver: Arc<Mutex<HostResolver>>,
+    tx: mpsc::Sender<PaneUpdate>,
+    pollers: Vec<Poller>,
+    next_id: u64,
 }
 
 impl PollerHandle {
     pub async fn stop(&mut self) {
-        let _ = self.shutdown.send(());
-        for task in self.tasks.drain(..) {
-            let _ = task.await;
+        for poller in self.pollers.drain(..) {
+            let _ = poller.shutdown.send(());
+            let _ = poller.task.await;
         }
     }
-}
 
-pub fn start_pollers(
-    config: &Config,
-    resolver: Arc<Mutex<HostResolver>>,
-    tx: mpsc::Sender<PaneUpdate>,
-) -> PollerHandle {
-    let (shutdown, _) = broadcast::channel(1);
-    let mut tasks = Vec::new();
+    pub fn is_current(&self, update: &PaneUpdate) -> bool {
+        self.pollers
+            .get(update.index)
+            .is_some_and(|poller| poller.id == update.poller)
+    }
+
+    pub async fn update(&mut self, config: &Config) -> usize {
+        let specs = PollerSpec::all(config);
+        let mut old: Vec<Option<Poller>> = self.pollers.drain(..).map(Some).collect();
+        let mut restarted = 0;
+        for (index, spec) in specs.into_iter().enumerate() {
+            let reuse = old.iter().position(|poller| {
+                poller
+                    .as_ref()
+                    .is_some_and(|poller| poller.spec.same_poll(&spec))
+            });
+            match reuse.and_then(|position| old[position].take()) {
+                Some(mut poller) => {
+                    poller.index.store(index, Ordering::Relaxed);
+                    poller.spec = spec;
+                    self.pollers.push(poller);
+                }
+                None => {
+                    if let Some(host) = &spec.host {
+                        self.resolver.lock().await.forget(&host.name);
+                    }
+                    let poller = self.spawn(index, spec);
+                    self.pollers.push(poller);
+                    restarted += 1;
+                }
+            }
+        }
+        for poller in old.into_iter().flatten() {
+            let _ = poller.shutdown.send(());
+            let _ = poller.task.await;
+        }
+        restarted
+    }
 
-    for (index, tracked) in config.tracked.iter().cloned().enumerate() {
-        let host = config.hosts.iter().find(|h| h.name == tracked.host).cloned();
-        let ssh_cfg = config.ssh.clone();
-        let refresh = Duration::from_millis(config.ui.refresh_ms);
-        let lines = config.ui.lines;
-        let join_lines = config.ui.join_lines;
-        let ansi = config.ui.ansi;
-        let mut shutdown_rx = shutdown.subscribe();
-        let tx = tx.clone();
-        let resolver = Arc::clone(&resolver);
+    fn spawn(&mut self, index: usize, spec: PollerSpec) -> Poller {
+        let id = self.next_id;
+        self.next_id += 1;
+        let (shutdown, mut shutdown_rx) = broadcast::channel(1);
+        let tx = self.tx.clone();
+        let resolver = Arc::clone(&self.resolver);
+        let index = Arc::new(AtomicUsize::new(index));
+        let task_index = Arc::clone(&index);
+        let PollerSpec {
+            tracked,
+            host,
+            ssh: ssh_cfg,
+            refresh,
+            lines,
+            join_lines,
+            ansi,
+        } = spec.clone();
 
-        let handle = tokio::spawn(async move {
+        let task = tokio::spawn(async move {
             loop {
                 let now = Instant::now();
+                let index = task_index.load(Ordering::Relaxed);
                 let update = match &host {
                     Some(host_cfg) => {
                         let target = {
@@ -61,33 +151,41 @@ pub fn start_pollers(
                             ).await {
                                 Ok(capture) => PaneUpdate {
                                     index,
+                                    poller: id,
                                     capture: Some(capture),
                                     status: PaneStatus::Ok,
                                     error: None,
+                                    target: Some(target),
                                     at: now,
                                 },
                                 Err(err) => PaneUpdate {
                                     index,
+                                    poller: id,
                                     capture: None,
                                     status: PaneStatus::Down,
                                     error: Some(err.to_string()),
+                                    target: Some(target),
                                     at: now,
                                 },
                             },
                             Err(err) => PaneUpdate {
                                 index,
+                                poller: id,
                                 capture: None,
                                 status: PaneStatus::Down,
                                 error: Some(err.to_string()),
+                                target: None,
                                 at: now,
                             },
                         }
                     }
                     None => PaneUpdate {
                         index,
+                        poller: id,
                         capture: None,
                         status: PaneStatus::Down,
                         error: Some("Unknown host".to_string()),
+                        target: None,
                         at: now,
                     },
                 };
@@ -101,8 +199,30 @@ pub fn start_pollers(
             }
         });
 
-        tasks.push(handle);
+        Poller {
+            id,
+            index,
+            spec,
+            shutdown,
+            task,
+        }
     }
+}
 
-    PollerHandle { shutdown, tasks }
+pub fn start_pollers(
+    config: &Config,
+    resolver: Arc<Mutex<HostResolver>>,
+    tx: mpsc::Sender<PaneUpdate>,
+) -> PollerHandle {
+    let mut handle = PollerHandle {
+        resolver,
+        tx,
+        pollers: Vec::new(),
+        next_id: 0,
+    };
+    for (index, spec) in PollerSpec::all(config).into_iter().enumerate() {
+        let poller = handle.spawn(index, spec);
+        handle.pollers.push(poller);
+    }
+    handle
 }
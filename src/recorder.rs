@@ -0,0 +1,128 @@
+use crate::config::{RecordConfig, TrackedPane};
+use crate::model::PaneState;
+use anyhow::{Context, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::{Instant, SystemTime, UNIX_EPOCH};
+
+const CAST_EXTENSION: &str = "cast";
+const CLEAR_SCREEN: &str = "\u{1b}[H\u{1b}[2J";
+
+#[derive(Serialize)]
+struct CastHeader {
+    version: u8,
+    width: u16,
+    height: u16,
+    timestamp: u64,
+    title: String,
+}
+
+struct CastFile {
+    file: File,
+    started: Instant,
+}
+
+pub struct Recorder {
+    session_dir: PathBuf,
+    files: HashMap<String, CastFile>,
+}
+
+impl Recorder {
+    pub fn new(config: &RecordConfig) -> Result<Option<Self>> {
+        if !config.enabled {
+            return Ok(None);
+        }
+        let started = chrono::Local::now().format("%Y%m%d-%H%M%S").to_string();
+        let session_dir = config.recordings_dir()?.join(started);
+        fs::create_dir_all(&session_dir).with_context(|| {
+            format!("Unable to create recordings directory: {}", session_dir.display())
+        })?;
+        Ok(Some(Self {
+            session_dir,
+            files: HashMap::new(),
+        }))
+    }
+
+    pub fn record(&mut self, pane: &PaneState) -> Result<()> {
+        let Some(capture) = &pane.last_capture else {
+            return Ok(());
+        };
+        let stem = pane.tracked.file_stem();
+        if !self.files.contains_key(&stem) {
+            let path = self.session_dir.join(format!("{stem}.{CAST_EXTENSION}"));
+            let cast = open_cast(&path, pane)?;
+            self.files.insert(stem.clone(), cast);
+        }
+        let Some(cast) = self.files.get_mut(&stem) else {
+            return Ok(());
+        };
+
+        let visible = visible_lines(&capture.lines, capture.height);
+        let data = format!("{CLEAR_SCREEN}{}", visible.join("\r\n"));
+        let elapsed = cast.started.elapsed().as_secs_f64();
+        let event = serde_json::to_string(&(elapsed, "o", data))
+            .context("Unable to serialize recording event")?;
+        writeln!(cast.file, "{event}").context("Unable to write recording event")?;
+        Ok(())
+    }
+}
+
+fn open_cast(path: &Path, pane: &PaneState) -> Result<CastFile> {
+    let mut file = OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(path)
+        .with_context(|| format!("Unable to open recording: {}", path.display()))?;
+    let capture = pane.last_capture.as_ref();
+    let width = capture.map(|c| c.width).filter(|w| *w > 0).unwrap_or(80);
+    let height = capture.map(|c| c.height).filter(|h| *h > 0).unwrap_or(24);
+    let header = CastHeader {
+        version: 2,
+        width,
+        height,
+        timestamp: SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs(),
+        title: format!(
+            "{} {}:{} {}",
+            pane.tracked.host, pane.tracked.session, pane.tracked.window, pane.tracked.pane_id
+        ),
+    };
+    let header = serde_json::to_string(&header).context("Unable to serialize recording header")?;
+    writeln!(file, "{header}").context("Unable to write recording header")?;
+    Ok(CastFile {
+        file,
+        started: Instant::now(),
+    })
+}
+
+fn visible_lines(lines: &[String], height: u16) -> &[String] {
+    let height = height as usize;
+    if height == 0 || lines.len() <= height {
+        lines
+    } else {
+        &lines[lines.len() - height..]
+    }
+}
+
+pub fn latest_recording(config: &RecordConfig, tracked: &TrackedPane) -> Result<Option<PathBuf>> {
+    let dir = config.recordings_dir()?;
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(None);
+    };
+    let mut sessions: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.is_dir())
+        .collect();
+    sessions.sort();
+    let file_name = format!("{}.{CAST_EXTENSION}", tracked.file_stem());
+    Ok(sessions
+        .into_iter()
+        .rev()
+        .map(|session| session.join(&file_name))
+        .find(|path| path.is_file()))
+}
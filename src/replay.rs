@@ -0,0 +1,311 @@
+use anyhow::{anyhow, Context, Result};
+use ansi_to_tui::IntoText as _;
+use chrono::{Local, TimeZone};
+use crossterm::event::{Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Direction, Layout};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Paragraph};
+use ratatui::Frame;
+use serde_json::Value;
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+const CLEAR_SCREEN: &str = "\u{1b}[2J";
+const DEFAULT_IDLE_LIMIT_SEC: f64 = 2.0;
+const SPEEDS: [f64; 6] = [0.5, 1.0, 2.0, 4.0, 16.0, 64.0];
+
+struct CastEvent {
+    time: f64,
+    data: String,
+}
+
+struct Cast {
+    title: Option<String>,
+    timestamp: Option<i64>,
+    height: usize,
+    idle_time_limit: Option<f64>,
+    events: Vec<CastEvent>,
+}
+
+fn load_cast(path: &Path) -> Result<Cast> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Unable to read recording: {}", path.display()))?;
+    let mut lines = contents.lines().filter(|line| !line.trim().is_empty());
+    let header_line = lines
+        .next()
+        .ok_or_else(|| anyhow!("Recording is empty: {}", path.display()))?;
+    let header: Value = serde_json::from_str(header_line)
+        .with_context(|| format!("Invalid asciicast header in {}", path.display()))?;
+    if header.get("version").and_then(Value::as_u64) != Some(2) {
+        return Err(anyhow!("Unsupported asciicast version in {}", path.display()));
+    }
+
+    let mut events = Vec::new();
+    for (number, line) in lines.enumerate() {
+        let event: Value = serde_json::from_str(line).with_context(|| {
+            format!("Invalid asciicast event on line {} of {}", number + 2, path.display())
+        })?;
+        let Some(fields) = event.as_array() else { continue; };
+        let time = fields.first().and_then(Value::as_f64);
+        let kind = fields.get(1).and_then(Value::as_str);
+        let data = fields.get(2).and_then(Value::as_str);
+        if let (Some(time), Some("o"), Some(data)) = (time, kind, data) {
+            events.push(CastEvent {
+                time,
+                data: data.to_string(),
+            });
+        }
+    }
+
+    Ok(Cast {
+        title: header.get("title").and_then(Value::as_str).map(str::to_string),
+        timestamp: header.get("timestamp").and_then(Value::as_i64),
+        height: header.get("height").and_then(Value::as_u64).unwrap_or(24) as usize,
+        idle_time_limit: header.get("idle_time_limit").and_then(Value::as_f64),
+        events,
+    })
+}
+
+pub async fn play(path: &Path, speed: f64, idle_limit: Option<f64>) -> Result<()> {
+    let cast = load_cast(path)?;
+    let speed = if speed > 0.0 { speed } else { 1.0 };
+    let idle_limit = idle_limit
+        .or(cast.idle_time_limit)
+        .unwrap_or(DEFAULT_IDLE_LIMIT_SEC);
+    let mut stdout = io::stdout();
+    let mut previous = 0.0f64;
+    for event in &cast.events {
+        let gap = (event.time - previous).clamp(0.0, idle_limit);
+        previous = event.time;
+        if gap > 0.0 {
+            tokio::time::sleep(Duration::from_secs_f64(gap / speed)).await;
+        }
+        stdout.write_all(event.data.as_bytes())?;
+        stdout.flush()?;
+    }
+    stdout.write_all(b"\r\n")?;
+    stdout.flush()?;
+    Ok(())
+}
+
+struct ReplayFrame {
+    time: f64,
+    lines: Vec<String>,
+}
+
+pub enum ReplayAction {
+    None,
+    Close,
+}
+
+pub struct ReplayState {
+    title: String,
+    timestamp: Option<i64>,
+    frames: Vec<ReplayFrame>,
+    index: usize,
+    playing: bool,
+    speed_index: usize,
+    position: f64,
+    last_tick: Instant,
+}
+
+impl ReplayState {
+    pub fn open(path: &Path) -> Result<Self> {
+        let cast = load_cast(path)?;
+        let frames = build_frames(&cast);
+        if frames.is_empty() {
+            return Err(anyhow!("Recording has no frames: {}", path.display()));
+        }
+        let title = cast
+            .title
+            .clone()
+            .unwrap_or_else(|| path.display().to_string());
+        let index = frames.len() - 1;
+        let position = frames[index].time;
+        Ok(Self {
+            title,
+            timestamp: cast.timestamp,
+            frames,
+            index,
+            playing: false,
+            speed_index: 1,
+            position,
+            last_tick: Instant::now(),
+        })
+    }
+
+    pub fn advance(&mut self) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.last_tick).as_secs_f64();
+        self.last_tick = now;
+        if !self.playing {
+            return;
+        }
+        self.position += elapsed * SPEEDS[self.speed_index];
+        while self.index + 1 < self.frames.len() && self.frames[self.index + 1].time <= self.position
+        {
+            self.index += 1;
+        }
+        if self.index + 1 >= self.frames.len() {
+            self.playing = false;
+        }
+    }
+
+    pub fn handle_event(&mut self, event: Event) -> ReplayAction {
+        let Event::Key(key) = event else { return ReplayAction::None; };
+        if key.kind != KeyEventKind::Press {
+            return ReplayAction::None;
+        }
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => return ReplayAction::Close,
+            KeyCode::Char(' ') => {
+                if !self.playing && self.index + 1 >= self.frames.len() {
+                    self.seek_index(0);
+                }
+                self.playing = !self.playing;
+                self.last_tick = Instant::now();
+            }
+            KeyCode::Left | KeyCode::Char('h') => {
+                self.seek_index(self.index.saturating_sub(1));
+            }
+            KeyCode::Right | KeyCode::Char('l') => self.seek_index(self.index + 1),
+            KeyCode::PageUp | KeyCode::Char('k') => self.seek_time(self.position - 60.0),
+            KeyCode::PageDown | KeyCode::Char('j') => self.seek_time(self.position + 60.0),
+            KeyCode::Home | KeyCode::Char('g') => self.seek_index(0),
+            KeyCode::End | KeyCode::Char('G') => self.seek_index(self.frames.len() - 1),
+            KeyCode::Char('+') | KeyCode::Char('=') => {
+                self.speed_index = (self.speed_index + 1).min(SPEEDS.len() - 1);
+            }
+            KeyCode::Char('-') => {
+                self.speed_index = self.speed_index.saturating_sub(1);
+            }
+            _ => {}
+        }
+        ReplayAction::None
+    }
+
+    fn seek_index(&mut self, index: usize) {
+        self.index = index.min(self.frames.len() - 1);
+        self.position = self.frames[self.index].time;
+    }
+
+    fn seek_time(&mut self, time: f64) {
+        let index = self
+            .frames
+            .iter()
+            .rposition(|frame| frame.time <= time)
+            .unwrap_or(0);
+        self.seek_index(index);
+        self.position = time.clamp(0.0, self.frames[self.frames.len() - 1].time);
+    }
+
+    pub fn draw(&self, f: &mut Frame) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(f.area());
+
+        let frame = &self.frames[self.index];
+        let raw = frame.lines.join("\n");
+        let text = raw.into_text().unwrap_or_else(|_| Text::from(raw.clone()));
+        let inner_height = chunks[0].height.saturating_sub(2) as usize;
+        let scroll = frame
+            .lines
+            .len()
+            .saturating_sub(inner_height)
+            .try_into()
+            .unwrap_or(0u16);
+
+        let state = if self.playing { "▶" } else { "⏸" };
+        let title = Line::from(vec![
+            Span::styled(
+                format!(" REPLAY {state} "),
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Magenta)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" "),
+            Span::styled(
+                self.title.clone(),
+                Style::default().fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            ),
+            Span::raw(" · "),
+            Span::styled(
+                self.frame_clock(frame),
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                format!(
+                    " · frame {}/{} · x{}",
+                    self.index + 1,
+                    self.frames.len(),
+                    SPEEDS[self.speed_index]
+                ),
+                Style::default().fg(Color::DarkGray),
+            ),
+        ]);
+        let block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::Magenta))
+            .title(title);
+        let paragraph = Paragraph::new(text).block(block).scroll((scroll, 0));
+        f.render_widget(paragraph, chunks[0]);
+
+        let footer = Line::from(Span::styled(
+            "Space play/pause  ←/→ frame  PgUp/PgDn ±1m  Home/End  +/- speed  q back",
+            Style::default().fg(Color::DarkGray),
+        ));
+        f.render_widget(Paragraph::new(footer), chunks[1]);
+    }
+
+    fn frame_clock(&self, frame: &ReplayFrame) -> String {
+        let offset = format_offset(frame.time);
+        let Some(start) = self.timestamp else {
+            return offset;
+        };
+        let millis = start * 1000 + (frame.time * 1000.0) as i64;
+        match Local.timestamp_millis_opt(millis).single() {
+            Some(when) => format!("{} (+{offset})", when.format("%Y-%m-%d %H:%M:%S")),
+            None => offset,
+        }
+    }
+}
+
+fn build_frames(cast: &Cast) -> Vec<ReplayFrame> {
+    let mut frames = Vec::new();
+    let mut screen = String::new();
+    for event in &cast.events {
+        match event.data.rfind(CLEAR_SCREEN) {
+            Some(pos) => {
+                screen.clear();
+                screen.push_str(&event.data[pos + CLEAR_SCREEN.len()..]);
+            }
+            None => screen.push_str(&event.data),
+        }
+        let lines: Vec<String> = screen
+            .split('\n')
+            .map(|line| line.trim_end_matches('\r').to_string())
+            .collect();
+        let keep = lines.len().saturating_sub(cast.height.max(1));
+        frames.push(ReplayFrame {
+            time: event.time,
+            lines: lines[keep..].to_vec(),
+        });
+        if keep > 0 {
+            screen = lines[keep..].join("\r\n");
+        }
+    }
+    frames
+}
+
+fn format_offset(secs: f64) -> String {
+    let total = secs.max(0.0) as u64;
+    let hours = total / 3600;
+    let minutes = (total % 3600) / 60;
+    let seconds = total % 60;
+    format!("{hours:02}:{minutes:02}:{seconds:02}")
+}
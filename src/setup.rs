@@ -1,5 +1,6 @@
 use crate::config::{Config, HostConfig, TrackedPane};
 use crate::ssh::HostResolver;
+use crate::sshconfig::{self, SshHost};
 use crate::tmux;
 use anyhow::Result;
 use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
@@ -117,10 +118,31 @@ enum FormField {
     Color,
 }
 
+#[derive(Debug)]
+struct ImportForm {
+    ssh_hosts: Vec<SshHost>,
+    candidates: Vec<HostConfig>,
+    selected: Vec<bool>,
+    index: usize,
+    group: bool,
+}
+
+impl ImportForm {
+    fn regroup(&mut self, config: &Config) {
+        self.candidates = sshconfig::to_host_configs(&self.ssh_hosts, self.group)
+            .into_iter()
+            .filter(|host| !sshconfig::is_configured(config, host))
+            .collect();
+        self.selected = vec![false; self.candidates.len()];
+        self.index = 0;
+    }
+}
+
 #[derive(Debug)]
 enum Modal {
     HostForm(HostForm),
     ConfirmDelete(usize),
+    ImportSsh(ImportForm),
 }
 
 #[derive(Debug)]
@@ -312,6 +334,7 @@ impl SetupState {
             KeyCode::Char('a') => self.open_add_host(),
             KeyCode::Char('e') => self.open_edit_host(),
             KeyCode::Char('d') => self.confirm_delete_host(),
+            KeyCode::Char('i') => self.open_import_ssh(),
             _ => {}
         }
 
@@ -334,6 +357,9 @@ impl SetupState {
                 {
                     label.push_str(" (local)");
                 }
+                if let Some(source) = &host.source {
+                    label.push_str(&format!(" (dynamic: {source})"));
+                }
                 ListItem::new(label)
             })
             .collect();
@@ -546,6 +572,7 @@ impl SetupState {
         spans.extend(hint("a", "add"));
         spans.extend(hint("e", "edit"));
         spans.extend(hint("d", "delete"));
+        spans.extend(hint("i", "import ssh"));
         spans.extend(hint("s", "save"));
         spans.extend(hint("q", "cancel"));
         if let Some(status) = &self.status {
@@ -562,6 +589,7 @@ impl SetupState {
         match modal {
             Modal::HostForm(form) => self.draw_host_form(f, form, popup),
             Modal::ConfirmDelete(index) => self.draw_confirm_delete(f, *index, popup),
+            Modal::ImportSsh(form) => self.draw_import_ssh(f, form, popup),
         }
     }
 
@@ -615,6 +643,50 @@ impl SetupState {
         f.render_widget(paragraph, area);
     }
 
+    fn draw_import_ssh(&self, f: &mut Frame, form: &ImportForm, area: Rect) {
+        let title = if form.group {
+            "Import from ~/.ssh/config (grouped by HostName)"
+        } else {
+            "Import from ~/.ssh/config"
+        };
+        let block = panel_block(title, true);
+        let inner = block.inner(area);
+        f.render_widget(block, area);
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(inner);
+
+        let items: Vec<ListItem> = form
+            .candidates
+            .iter()
+            .zip(&form.selected)
+            .map(|(host, selected)| {
+                let marker = if *selected { "[x]" } else { "[ ]" };
+                let label = sshconfig::describe(&form.ssh_hosts, host);
+                ListItem::new(format!("{marker} {label}"))
+            })
+            .collect();
+        let items = if items.is_empty() {
+            vec![ListItem::new(Span::styled(
+                "No new hosts found",
+                Style::default().fg(ACCENT_DIM),
+            ))]
+        } else {
+            items
+        };
+        let mut list_state = ListState::default();
+        if !form.candidates.is_empty() {
+            list_state.select(Some(form.index));
+        }
+        let list = List::new(items).highlight_style(highlight_style(true));
+        f.render_stateful_widget(list, chunks[0], &mut list_state);
+        f.render_widget(
+            Paragraph::new("Space: toggle  a: all  g: group  Enter: import  Esc: cancel"),
+            chunks[1],
+        );
+    }
+
     fn handle_modal_event(&mut self, event: Event) -> Result<SetupAction> {
         let Event::Key(key) = event else { return Ok(SetupAction::None); };
         if key.kind != KeyEventKind::Press {
@@ -629,6 +701,7 @@ impl SetupState {
         let keep_open = match &mut modal {
             Modal::HostForm(form) => self.handle_host_form_event(form, key)?,
             Modal::ConfirmDelete(index) => self.handle_confirm_delete_event(*index, key)?,
+            Modal::ImportSsh(form) => self.handle_import_ssh_event(form, key),
         };
 
         if keep_open {
@@ -670,11 +743,12 @@ impl SetupState {
                 };
             }
             KeyCode::Enter => {
-                if let Some(host) = self.build_host_from_form(form) {
+                if let Some(mut host) = self.build_host_from_form(form) {
                     match form.mode {
                         FormMode::Add => self.config.hosts.push(host),
                         FormMode::Edit(index) => {
                             if let Some(existing) = self.config.hosts.get_mut(index) {
+                                host.ssh = existing.ssh.take();
                                 *existing = host;
                             }
                         }
@@ -729,6 +803,44 @@ impl SetupState {
         }
     }
 
+    fn handle_import_ssh_event(&mut self, form: &mut ImportForm, key: KeyEvent) -> bool {
+        let count = form.candidates.len();
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => return false,
+            KeyCode::Up | KeyCode::Char('k') => form.index = form.index.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => {
+                form.index = (form.index + 1).min(count.saturating_sub(1));
+            }
+            KeyCode::Char(' ') => {
+                if let Some(selected) = form.selected.get_mut(form.index) {
+                    *selected = !*selected;
+                }
+            }
+            KeyCode::Char('a') => {
+                let all = form.selected.iter().all(|selected| *selected);
+                form.selected.iter_mut().for_each(|selected| *selected = !all);
+            }
+            KeyCode::Char('g') => {
+                form.group = !form.group;
+                form.regroup(&self.config);
+            }
+            KeyCode::Enter => {
+                let mut imported = 0;
+                for (host, selected) in form.candidates.iter().zip(&form.selected) {
+                    if *selected {
+                        self.config.hosts.push(host.clone());
+                        imported += 1;
+                    }
+                }
+                self.status = Some(format!("Imported {imported} host(s) from ssh config"));
+                self.ensure_host_loaded();
+                return false;
+            }
+            _ => {}
+        }
+        true
+    }
+
     fn build_host_from_form(&mut self, form: &mut HostForm) -> Option<HostConfig> {
         form.error = None;
         let name = form.name.trim().to_string();
@@ -755,9 +867,11 @@ impl SetupState {
         Some(HostConfig {
             name,
             targets,
-            strategy: Some("auto".to_string()),
+            strategy: None,
             color,
             tags: None,
+            ssh: None,
+            source: None,
         })
     }
 
@@ -793,6 +907,25 @@ impl SetupState {
         }));
     }
 
+    fn open_import_ssh(&mut self) {
+        let ssh_hosts = match sshconfig::default_path().and_then(|path| sshconfig::load(&path)) {
+            Ok(ssh_hosts) => ssh_hosts,
+            Err(err) => {
+                self.status = Some(format!("{err:#}"));
+                return;
+            }
+        };
+        let mut form = ImportForm {
+            ssh_hosts,
+            candidates: Vec::new(),
+            selected: Vec::new(),
+            index: 0,
+            group: false,
+        };
+        form.regroup(&self.config);
+        self.modal = Some(Modal::ImportSsh(form));
+    }
+
     fn confirm_delete_host(&mut self) {
         let Some(host) = self.config.hosts.get(self.host_index) else { return; };
         if is_local_host(host) {
@@ -1002,6 +1135,14 @@ impl SetupState {
                 label: "macOS notify on stop",
                 kind: SettingKind::Toggle(self.config.ui.macos_notification_on_stop),
             },
+            SettingItem {
+                label: "Linux notify on stop",
+                kind: SettingKind::Toggle(self.config.ui.linux_notification_on_stop),
+            },
+            SettingItem {
+                label: "Terminal notify on stop",
+                kind: SettingKind::Toggle(self.config.ui.terminal_notification_on_stop),
+            },
             SettingItem {
                 label: "Notify only when inactive",
                 kind: SettingKind::Toggle(self.config.ui.macos_notify_only_when_inactive),
@@ -1025,7 +1166,11 @@ impl SetupState {
             3 => self.config.ui.bell_on_stop = !self.config.ui.bell_on_stop,
             4 => self.config.ui.macos_notification_on_stop =
                 !self.config.ui.macos_notification_on_stop,
-            5 => self.config.ui.macos_notify_only_when_inactive =
+            5 => self.config.ui.linux_notification_on_stop =
+                !self.config.ui.linux_notification_on_stop,
+            6 => self.config.ui.terminal_notification_on_stop =
+                !self.config.ui.terminal_notification_on_stop,
+            7 => self.config.ui.macos_notify_only_when_inactive =
                 !self.config.ui.macos_notify_only_when_inactive,
             _ => {}
         }
@@ -1033,14 +1178,14 @@ impl SetupState {
 
     fn adjust_setting(&mut self, delta: i64) {
         match self.setting_index {
-            6 => {
+            8 => {
                 let next = adjust_u64(self.config.ui.activity_active_window_sec, delta, 1, 600);
                 self.config.ui.activity_active_window_sec = next;
                 if self.config.ui.activity_idle_after_sec <= next {
                     self.config.ui.activity_idle_after_sec = next + 1;
                 }
             }
-            7 => {
+            9 => {
                 let next = adjust_u64(self.config.ui.activity_idle_after_sec, delta, 2, 600);
                 self.config.ui.activity_idle_after_sec = next;
             }
@@ -1132,7 +1277,7 @@ impl SetupState {
                 panes_by_window: HashMap::new(),
             },
         );
-        let ssh_cfg = self.config.ssh.clone();
+        let ssh_cfg = self.config.ssh.for_host(&host_cfg);
         let tx = self.msg_tx.clone();
         let host_for_error = host_name.clone();
         tokio::spawn(async move {
@@ -1196,10 +1341,10 @@ impl SetupState {
             Some(host) => host.clone(),
             None => return,
         };
-        let ssh_cfg = self.config.ssh.clone();
+        let ssh_cfg = self.config.ssh.for_host(&host_cfg);
         let lines = self.config.ui.lines.min(20);
         let join_lines = self.config.ui.join_lines;
-        let ansi = self.config.ui.ansi;
+        let ansi = self.config.ui.ansi();
         let tx = self.msg_tx.clone();
         tokio::spawn(async move {
             let mut resolver = HostResolver::new();
@@ -1,4 +1,4 @@
-use crate::config::{HostConfig, SshConfig};
+use crate::config::{self, HostConfig, SshConfig};
 use anyhow::{anyhow, Context, Result};
 use std::collections::HashMap;
 use std::process::Stdio;
@@ -26,6 +26,10 @@ impl HostResolver {
         }
     }
 
+    pub fn forget(&mut self, host: &str) {
+        self.cache.remove(host);
+    }
+
     pub async fn resolve_target(&mut self, host: &HostConfig, ssh: &SshConfig) -> Result<String> {
         if let Some(entry) = self.cache.get(&host.name) {
             if entry.checked_at.elapsed() < CACHE_TTL {
@@ -112,6 +116,23 @@ pub fn build_ssh_args(ssh: &SshConfig) -> Vec<String> {
         args.push("-o".to_string());
         args.push(format!("ControlPath={}", control_path()));
     }
+    if let Some(user) = &ssh.user {
+        args.push("-l".to_string());
+        args.push(user.clone());
+    }
+    if let Some(port) = ssh.port {
+        args.push("-p".to_string());
+        args.push(port.to_string());
+    }
+    if let Some(identity_file) = &ssh.identity_file {
+        args.push("-i".to_string());
+        args.push(config::expand_home(identity_file).display().to_string());
+    }
+    if let Some(proxy_jump) = &ssh.proxy_jump {
+        args.push("-J".to_string());
+        args.push(proxy_jump.clone());
+    }
+    args.extend(ssh.extra_args.iter().cloned());
     args
 }
 
@@ -0,0 +1,177 @@
+use crate::config::{self, HostConfig};
+use anyhow::{anyhow, Context, Result};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshHost {
+    pub alias: String,
+    pub hostname: Option<String>,
+}
+
+pub fn default_path() -> Result<PathBuf> {
+    let home = std::env::var_os("HOME").ok_or_else(|| anyhow!("HOME not set"))?;
+    Ok(PathBuf::from(home).join(".ssh").join("config"))
+}
+
+pub fn load(path: &Path) -> Result<Vec<SshHost>> {
+    let base = path
+        .parent()
+        .map(Path::to_path_buf)
+        .unwrap_or_else(|| PathBuf::from("."));
+    let mut hosts = Vec::new();
+    parse_file(path, &base, 0, &mut hosts)?;
+    Ok(hosts)
+}
+
+fn parse_file(path: &Path, base: &Path, depth: usize, hosts: &mut Vec<SshHost>) -> Result<()> {
+    if depth > MAX_INCLUDE_DEPTH {
+        return Err(anyhow!("Too many nested Include directives at {}", path.display()));
+    }
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Unable to read ssh config: {}", path.display()))?;
+    let mut current: Vec<usize> = Vec::new();
+    for line in contents.lines() {
+        let Some((keyword, value)) = split_line(line) else {
+            continue;
+        };
+        match keyword.as_str() {
+            "host" => {
+                current.clear();
+                for alias in words(value) {
+                    if alias.contains(['*', '?', '!']) {
+                        continue;
+                    }
+                    match hosts.iter().position(|host| host.alias == alias) {
+                        Some(index) => current.push(index),
+                        None => {
+                            hosts.push(SshHost {
+                                alias: alias.to_string(),
+                                hostname: None,
+                            });
+                            current.push(hosts.len() - 1);
+                        }
+                    }
+                }
+            }
+            "match" => current.clear(),
+            "hostname" => {
+                for index in &current {
+                    let host = &mut hosts[*index];
+                    if host.hostname.is_none() {
+                        host.hostname = Some(value.trim_matches('"').replace("%h", &host.alias));
+                    }
+                }
+            }
+            "include" => {
+                for pattern in words(value) {
+                    let pattern = base.join(config::expand_home(pattern));
+                    let has_glob = pattern
+                        .file_name()
+                        .is_some_and(|name| name.to_string_lossy().contains(['*', '?']));
+                    let files = if has_glob {
+                        config::expand_glob(&pattern)
+                    } else if pattern.is_file() {
+                        vec![pattern]
+                    } else {
+                        Vec::new()
+                    };
+                    for file in files {
+                        parse_file(&file, base, depth + 1, hosts)?;
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn split_line(line: &str) -> Option<(String, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let split = line.find(|ch: char| ch.is_whitespace() || ch == '=')?;
+    let (keyword, rest) = line.split_at(split);
+    let value = rest
+        .trim_start()
+        .strip_prefix('=')
+        .unwrap_or(rest)
+        .trim();
+    Some((keyword.to_lowercase(), value))
+}
+
+fn words(value: &str) -> impl Iterator<Item = &str> {
+    value
+        .split_whitespace()
+        .map(|word| word.trim_matches('"'))
+        .filter(|word| !word.is_empty())
+}
+
+pub fn to_host_configs(hosts: &[SshHost], group: bool) -> Vec<HostConfig> {
+    if !group {
+        return hosts
+            .iter()
+            .map(|host| host_config(&host.alias, vec![host.alias.clone()]))
+            .collect();
+    }
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    let mut order = Vec::new();
+    for host in hosts {
+        let machine = host
+            .hostname
+            .as_deref()
+            .unwrap_or(&host.alias)
+            .to_lowercase();
+        let aliases = groups.entry(machine.clone()).or_default();
+        if aliases.is_empty() {
+            order.push(machine);
+        }
+        aliases.push(host.alias.clone());
+    }
+    order
+        .into_iter()
+        .filter_map(|machine| groups.remove(&machine))
+        .map(|aliases| host_config(&aliases[0].clone(), aliases))
+        .collect()
+}
+
+fn host_config(name: &str, targets: Vec<String>) -> HostConfig {
+    HostConfig {
+        name: name.to_string(),
+        targets,
+        strategy: None,
+        color: None,
+        tags: None,
+        ssh: None,
+        source: None,
+    }
+}
+
+pub fn describe(ssh_hosts: &[SshHost], host: &HostConfig) -> String {
+    let hostname = ssh_hosts
+        .iter()
+        .find(|ssh| host.targets.contains(&ssh.alias))
+        .and_then(|ssh| ssh.hostname.as_deref());
+    let aliases = host.targets.join(", ");
+    match hostname {
+        Some(hostname) if host.targets.iter().all(|target| target != hostname) => {
+            format!("{aliases} → {hostname}")
+        }
+        _ => aliases,
+    }
+}
+
+pub fn is_configured(config: &config::Config, host: &HostConfig) -> bool {
+    config.hosts.iter().any(|existing| {
+        existing.name == host.name
+            || existing
+                .targets
+                .iter()
+                .any(|target| host.targets.contains(target))
+    })
+}
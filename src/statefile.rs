@@ -0,0 +1,135 @@
+use crate::config::{self, TrackedPane};
+use crate::model::{self, AppState, AttentionState, PaneCapture, PaneEdits};
+use anyhow::{anyhow, Context, Result};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SavedState {
+    pub focused: Option<TrackedPane>,
+    pub zoomed: bool,
+    pub muted_hosts: Vec<String>,
+    pub muted_panes: Vec<TrackedPane>,
+    #[serde(flatten)]
+    pub edits: PaneEdits,
+    pub panes: Vec<SavedPane>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SavedPane {
+    #[serde(flatten)]
+    pub tracked: TrackedPane,
+    pub attention: AttentionState,
+    pub capture: Option<PaneCapture>,
+}
+
+impl SavedState {
+    pub fn capture(state: &AppState) -> Self {
+        Self {
+            focused: state.panes.get(state.focused).map(|pane| pane.tracked.clone()),
+            zoomed: state.zoomed,
+            muted_hosts: state.muted_hosts.clone(),
+            muted_panes: state.muted_panes.clone(),
+            edits: state.pane_edits.clone(),
+            panes: state
+                .panes
+                .iter()
+                .enumerate()
+                .map(|(index, pane)| SavedPane {
+                    tracked: pane.tracked.clone(),
+                    attention: state
+                        .attention
+                        .get(index)
+                        .copied()
+                        .unwrap_or(AttentionState::None),
+                    capture: pane.last_capture.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    pub fn restore(&self, state: &mut AppState) {
+        state.zoomed = self.zoomed;
+        state.muted_hosts = self.muted_hosts.clone();
+        state.muted_panes = self.muted_panes.clone();
+        state.apply_pane_edits(self.edits.clone());
+        if let Some(focused) = &self.focused
+            && let Some(index) = state.pane_index(focused)
+        {
+            state.focused = index;
+        }
+        for saved in &self.panes {
+            let Some(index) = state.pane_index(&saved.tracked) else {
+                continue;
+            };
+            state.set_attention(index, saved.attention);
+            let pane = &mut state.panes[index];
+            if pane.last_capture.is_none()
+                && let Some(capture) = &saved.capture
+            {
+                pane.last_hash = Some(model::hash_capture(capture));
+                pane.last_capture = Some(capture.clone());
+            }
+        }
+    }
+}
+
+// Each config keeps its own state: the default config uses state.json, a profile
+// state-<profile>.json and any other config file a name built from its path.
+pub fn state_path(config_path: &Path) -> Result<PathBuf> {
+    let dir = match std::env::var_os("XDG_STATE_HOME").filter(|dir| !dir.is_empty()) {
+        Some(dir) => PathBuf::from(dir),
+        None => {
+            let home = std::env::var_os("HOME").ok_or_else(|| anyhow!("HOME not set"))?;
+            PathBuf::from(home).join(".local").join("state")
+        }
+    };
+    let config_dir = config::config_dir()?;
+    let name = if config_path == config_dir.join("config.toml") {
+        "state.json".to_string()
+    } else if config_path.parent() == Some(config_dir.join("profiles").as_path())
+        && config_path.extension().is_some_and(|ext| ext == "toml")
+    {
+        let profile = config_path.file_stem().unwrap_or_default().to_string_lossy();
+        format!("state-{profile}.json")
+    } else {
+        let absolute = fs::canonicalize(config_path).unwrap_or_else(|_| config_path.to_path_buf());
+        let slug: String = absolute
+            .with_extension("")
+            .to_string_lossy()
+            .chars()
+            .map(|ch| if ch.is_ascii_alphanumeric() { ch } else { '_' })
+            .collect();
+        format!("state-{}.json", slug.trim_matches('_'))
+    };
+    Ok(dir.join("fleetmux").join(name))
+}
+
+pub fn load(config_path: &Path) -> Result<SavedState> {
+    let path = state_path(config_path)?;
+    if !path.exists() {
+        return Ok(SavedState::default());
+    }
+    let contents = fs::read_to_string(&path)
+        .with_context(|| format!("Unable to read state file: {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Unable to parse state file: {}", path.display()))
+}
+
+pub fn save(config_path: &Path, saved: &SavedState) -> Result<()> {
+    let path = state_path(config_path)?;
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).with_context(|| {
+            format!("Unable to create state directory: {}", parent.display())
+        })?;
+    }
+    let contents = serde_json::to_string(saved).context("Unable to serialize state")?;
+    let tmp = path.with_extension("json.tmp");
+    fs::write(&tmp, contents)
+        .with_context(|| format!("Unable to write state file: {}", tmp.display()))?;
+    fs::rename(&tmp, &path)
+        .with_context(|| format!("Unable to write state file: {}", path.display()))?;
+    Ok(())
+}
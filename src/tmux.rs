@@ -36,7 +36,7 @@ pub async fn capture_pane(
     }
     capture_cmd.push_str(&format!("-t {pane_id} -S -{lines}"));
     let cmd = format!(
-        "tmux display-message -p -t {pane_id} '#{{pane_current_command}}\t#{{pane_title}}' \
+        "tmux display-message -p -t {pane_id} '#{{pane_current_command}}\t#{{pane_title}}\t#{{pane_width}}\t#{{pane_height}}\t#{{pane_dead}}\t#{{pane_dead_status}}\t#{{@fleetmux_exit}}' \
          && {capture_cmd}"
     );
     let output = ssh::run_ssh_command(target, ssh_cfg, &cmd)
@@ -69,10 +69,30 @@ fn parse_capture(output: &str) -> Result<PaneCapture> {
     let mut header_parts = header.split('\t');
     let command = header_parts.next().unwrap_or("").to_string();
     let title = header_parts.next().unwrap_or("").to_string();
+    let width = header_parts
+        .next()
+        .and_then(|val| val.parse::<u16>().ok())
+        .unwrap_or(0);
+    let height = header_parts
+        .next()
+        .and_then(|val| val.parse::<u16>().ok())
+        .unwrap_or(0);
+    let dead = header_parts.next() == Some("1");
+    let dead_status = header_parts
+        .next()
+        .and_then(|val| val.trim().parse::<i32>().ok());
+    let exit_status = header_parts
+        .next()
+        .and_then(|val| val.trim().parse::<i32>().ok());
     let body_lines = lines.map(|line| line.to_string()).collect();
     Ok(PaneCapture {
         command,
         title,
+        width,
+        height,
+        dead,
+        dead_status,
+        exit_status,
         lines: body_lines,
     })
 }
@@ -3,7 +3,7 @@ use ansi_to_tui::IntoText as _;
 use ratatui::layout::{Constraint, Direction, Layout, Rect};
 use ratatui::style::{Color, Modifier, Style};
 use ratatui::text::{Line, Span, Text};
-use ratatui::widgets::{Block, BorderType, Borders, Paragraph, Wrap};
+use ratatui::widgets::{Block, BorderType, Borders, Clear, Paragraph, Wrap};
 use ratatui::Frame;
 use std::time::{Duration, SystemTime, UNIX_EPOCH};
 const SPINNER_FRAMES: [&str; 8] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧"];
@@ -38,11 +38,36 @@ pub fn draw(f: &mut Frame, state: &AppState) {
         }
     }
 
+    if let Some(message) = state.current_status() {
+        draw_status(f, main_area, message);
+    }
+
     if state.show_help {
         draw_help(f, area);
     }
 }
 
+fn draw_status(f: &mut Frame, area: Rect, message: &str) {
+    if area.height == 0 {
+        return;
+    }
+    let rect = Rect {
+        x: area.x,
+        y: area.y + area.height - 1,
+        width: area.width,
+        height: 1,
+    };
+    let paragraph = Paragraph::new(Line::from(Span::styled(
+        format!(" {message} "),
+        Style::default()
+            .fg(Color::Black)
+            .bg(Color::Yellow)
+            .add_modifier(Modifier::BOLD),
+    )));
+    f.render_widget(Clear, rect);
+    f.render_widget(paragraph, rect);
+}
+
 fn draw_tile(f: &mut Frame, state: &AppState, index: usize, area: Rect, focused: bool) {
     let pane = match state.panes.get(index) {
         Some(pane) => pane,
@@ -78,21 +103,30 @@ fn draw_tile(f: &mut Frame, state: &AppState, index: usize, area: Rect, focused:
     let host_style = Style::default()
         .fg(title_color)
         .add_modifier(Modifier::BOLD);
-    let (active_window, idle_after) = state.config.ui.activity_windows();
+    let activity = state.config.ui.activity_windows();
+    let (active_window, idle_after) = activity;
+    let host = if state.host_dynamic(&pane.tracked.host) {
+        format!("{} ⟳", pane.tracked.host)
+    } else {
+        pane.tracked.host.clone()
+    };
     let title = build_title(
-        pane.tracked.host.as_str(),
+        &host,
         pane,
         host_style,
         title_color,
         state.config.ui.compact,
-        focused,
-        state
-            .attention
-            .get(index)
-            .copied()
-            .unwrap_or(crate::model::AttentionState::None),
-        active_window,
-        idle_after,
+        TitleState {
+            focused,
+            attention: state
+                .attention
+                .get(index)
+                .copied()
+                .unwrap_or(crate::model::AttentionState::None),
+            muted: state.pane_muted(&pane.tracked),
+            escalation: state.escalation(index),
+        },
+        activity,
     );
 
     let block = Block::default()
@@ -120,17 +154,28 @@ fn draw_tile(f: &mut Frame, state: &AppState, index: usize, area: Rect, focused:
     f.render_widget(paragraph, area);
 }
 
+struct TitleState {
+    focused: bool,
+    attention: crate::model::AttentionState,
+    muted: bool,
+    escalation: Option<crate::model::Escalation>,
+}
+
 fn build_title(
     host: &str,
     pane: &crate::model::PaneState,
     host_style: Style,
     title_color: Color,
     compact: bool,
-    focused: bool,
-    attention: crate::model::AttentionState,
-    active_window: Duration,
-    idle_after: Duration,
+    title_state: TitleState,
+    (active_window, idle_after): (Duration, Duration),
 ) -> Line<'static> {
+    let TitleState {
+        focused,
+        attention,
+        muted,
+        escalation,
+    } = title_state;
     let session_window = format!("{}:{}", pane.tracked.session, pane.tracked.window);
     let pane_id = format_pane_id(&pane.tracked.pane_id);
     let title_bg = if focused {
@@ -151,14 +196,43 @@ fn build_title(
     ));
     if attention != crate::model::AttentionState::None {
         let (label, color) = match attention {
-            crate::model::AttentionState::Manual => ("● ATTN", Color::Yellow),
-            crate::model::AttentionState::Done => ("● DONE", Color::Green),
-            crate::model::AttentionState::None => ("", Color::Yellow),
+            crate::model::AttentionState::Manual => ("● ATTN".to_string(), Color::Yellow),
+            crate::model::AttentionState::Done => match &pane.finished {
+                Some(finished) => match finished.exit_status {
+                    Some(0) => (format!("● DONE {} ✓", finished.command), Color::Green),
+                    Some(code) => (format!("● EXIT {code} {}", finished.command), Color::Red),
+                    None => (format!("● DONE {}", finished.command), Color::Green),
+                },
+                None => ("● DONE".to_string(), Color::Green),
+            },
+            crate::model::AttentionState::Input => ("● INPUT".to_string(), Color::Magenta),
+            crate::model::AttentionState::None => (String::new(), Color::Yellow),
         };
+        let mut style = Style::default().fg(color).add_modifier(Modifier::BOLD);
+        if attention == crate::model::AttentionState::Input {
+            style = style.add_modifier(Modifier::REVERSED);
+        }
+        spans.push(title_raw(" ", title_bg));
+        spans.push(title_span(label, style, title_bg));
+    }
+    if let Some(escalation) = escalation {
+        let (label, color) = match escalation {
+            crate::model::Escalation::Renotify(left) => {
+                (format!("⏱ {}", format_countdown(left)), Color::Yellow)
+            }
+            crate::model::Escalation::Escalate(left) => {
+                (format!("⏱ {} ↑", format_countdown(left)), Color::Red)
+            }
+            crate::model::Escalation::Escalated => ("↑ ESCALATED".to_string(), Color::Red),
+        };
+        spans.push(title_raw(" ", title_bg));
+        spans.push(title_span(label, Style::default().fg(color), title_bg));
+    }
+    if muted {
         spans.push(title_raw(" ", title_bg));
         spans.push(title_span(
-            label.to_string(),
-            Style::default().fg(color).add_modifier(Modifier::BOLD),
+            "⊘ MUTED".to_string(),
+            Style::default().fg(Color::DarkGray).add_modifier(Modifier::BOLD),
             title_bg,
         ));
     }
@@ -272,7 +346,7 @@ fn build_content(
     idle_after: Duration,
 ) -> Content {
     let pane = &state.panes[index];
-    if state.config.ui.ansi {
+    if state.config.ui.ansi() {
         let raw = build_raw_content(state, pane, index, compact, active_window, idle_after);
         let line_count = raw.lines().count().max(1);
         let text = raw.into_text().unwrap_or_else(|_| Text::from(raw));
@@ -540,6 +614,11 @@ fn grid_layout(area: Rect, count: usize) -> Vec<Rect> {
     tiles.into_iter().take(count).collect()
 }
 
+fn format_countdown(left: Duration) -> String {
+    let secs = left.as_secs();
+    format!("{}:{:02}", secs / 60, secs % 60)
+}
+
 fn draw_help(f: &mut Frame, area: Rect) {
     let help = vec![
         Line::from("Keys:"),
@@ -548,7 +627,9 @@ fn draw_help(f: &mut Frame, area: Rect) {
         Line::from("  Tab   Next tile"),
         Line::from("  Enter   Take control"),
         Line::from("  !   Mark attention"),
+        Line::from("  a   Acknowledge attention"),
         Line::from("  b   Toggle bookmark"),
+        Line::from("  m/M   Mute pane / host"),
         Line::from("  1-9/0   Jump to bookmark"),
         Line::from("  r   Reload config"),
         Line::from("  e   Edit config"),
@@ -556,6 +637,7 @@ fn draw_help(f: &mut Frame, area: Rect) {
         Line::from("  s   Setup"),
         Line::from("  c   Toggle compact mode"),
         Line::from("  z   Zoom focused tile"),
+        Line::from("  p   Replay focused pane recording"),
         Line::from("  ?   Toggle help"),
     ];
 
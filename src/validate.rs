@@ -0,0 +1,522 @@
+use crate::config::Config;
+use crate::model;
+use regex::Regex;
+use std::collections::HashMap;
+use std::fmt;
+use std::ops::Range;
+use toml_edit::{ImDocument, Item, TableLike, Value};
+
+#[derive(Clone, Debug)]
+pub struct Diagnostic {
+    pub line: usize,
+    pub column: usize,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}:{}: {}", self.line, self.column, self.message)
+    }
+}
+
+const RANGES: &[(&str, &str, i64, i64)] = &[
+    ("ui", "refresh_ms", 50, 600_000),
+    ("ui", "lines", 1, 10_000),
+    ("ui", "activity_active_window_sec", 1, 86_400),
+    ("ui", "activity_idle_after_sec", 1, 86_400),
+    ("ui", "notify_snooze_sec", 0, 86_400),
+    ("ui", "linux_notify_timeout_ms", -1, 3_600_000),
+    ("ssh", "connect_timeout_sec", 1, 600),
+    ("ssh", "control_persist_sec", 0, 604_800),
+    ("ssh", "port", 1, 65_535),
+    ("hosts.ssh", "connect_timeout_sec", 1, 600),
+    ("hosts.ssh", "port", 1, 65_535),
+    ("logs", "keep", 0, 1_000),
+    ("logs", "max_age_days", 0, 36_500),
+    ("webhooks", "timeout_sec", 1, 600),
+    ("webhooks", "retries", 0, 10),
+    ("webhooks", "rate_limit_per_min", 0, 10_000),
+    ("hooks", "timeout_sec", 1, 86_400),
+    ("hooks", "lines", 0, 10_000),
+    ("inventory", "refresh_sec", 10, 604_800),
+    ("inventory", "timeout_sec", 1, 600),
+    ("notifications.escalation", "renotify_after_min", 0, 10_080),
+    ("notifications.escalation", "escalate_after_min", 0, 10_080),
+];
+
+fn known_keys(path: &str) -> Option<&'static [&'static str]> {
+    Some(match path {
+        "" => &[
+            "version",
+            "include",
+            "ui",
+            "colors",
+            "ssh",
+            "local",
+            "record",
+            "logs",
+            "api",
+            "webhooks",
+            "notifications",
+            "hooks",
+            "hosts",
+            "inventory",
+            "tracked",
+            "bookmarks",
+        ],
+        "ui" => &[
+            "refresh_ms",
+            "lines",
+            "layout",
+            "theme",
+            "compact",
+            "ansi",
+            "join_lines",
+            "activity_active_window_sec",
+            "activity_idle_after_sec",
+            "bell_on_stop",
+            "macos_notification_on_stop",
+            "macos_notify_only_when_inactive",
+            "macos_notify_ignore_apps",
+            "macos_notify_sender",
+            "linux_notification_on_stop",
+            "linux_notify_urgency",
+            "linux_notify_timeout_ms",
+            "linux_notify_actions",
+            "terminal_notification_on_stop",
+            "terminal_notify_format",
+            "terminal_notify_passthrough",
+            "notify_snooze_sec",
+        ],
+        "colors" => &["default_host_palette"],
+        "ssh" => &[
+            "connect_timeout_sec",
+            "control_master",
+            "control_persist_sec",
+            "path_extra",
+            "user",
+            "port",
+            "identity_file",
+            "proxy_jump",
+            "extra_args",
+        ],
+        "local" => &["enabled", "name", "color"],
+        "record" => &["enabled", "dir"],
+        "logs" => &[
+            "enabled",
+            "dir",
+            "timestamps",
+            "max_bytes",
+            "keep",
+            "max_age_days",
+        ],
+        "api" => &["enabled", "bind"],
+        "webhooks" => &[
+            "name",
+            "url",
+            "events",
+            "template",
+            "headers",
+            "timeout_sec",
+            "retries",
+            "rate_limit_per_min",
+        ],
+        "notifications" => &[
+            "command",
+            "error_patterns",
+            "input_patterns",
+            "routes",
+            "quiet_hours",
+            "escalation",
+        ],
+        "notifications.routes" => &["events", "backends", "hosts", "tags", "panes"],
+        "notifications.quiet_hours" => &["days", "start", "end", "events"],
+        "notifications.escalation" => &[
+            "events",
+            "renotify_after_min",
+            "escalate_after_min",
+            "backends",
+        ],
+        "hooks" => &[
+            "name",
+            "on",
+            "command",
+            "timeout_sec",
+            "lines",
+            "hosts",
+            "tags",
+            "panes",
+        ],
+        "hosts" => &["name", "targets", "strategy", "color", "tags", "ssh"],
+        "hosts.ssh" => &[
+            "user",
+            "port",
+            "identity_file",
+            "proxy_jump",
+            "connect_timeout_sec",
+            "path_extra",
+            "extra_args",
+        ],
+        "inventory" => &[
+            "name",
+            "command",
+            "file",
+            "format",
+            "refresh_sec",
+            "timeout_sec",
+            "tags",
+        ],
+        "tracked" | "bookmarks" => &["host", "session", "window", "pane_id", "label"],
+        _ => return None,
+    })
+}
+
+pub fn check(contents: &str, included_hosts: &[String]) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        contents,
+        included_hosts,
+        diagnostics: Vec::new(),
+    };
+    let doc = match ImDocument::parse(contents) {
+        Ok(doc) => doc,
+        Err(err) => {
+            checker.push(err.span(), err.message().trim().to_string());
+            return checker.finish();
+        }
+    };
+    let root = doc.as_table();
+    checker.unknown_keys(root, "");
+    checker.hosts(root);
+    checker.inventory(root);
+    checker.tracked(root);
+    checker.colors(root);
+    checker.patterns(root);
+    checker.ranges(root);
+    if let Err(err) = toml::from_str::<Config>(contents) {
+        let line = err.span().map(|span| checker.line_of(span));
+        if !checker
+            .diagnostics
+            .iter()
+            .any(|(_, found)| Some(found.line) == line)
+        {
+            checker.push(err.span(), err.message().trim().to_string());
+        }
+    }
+    checker.finish()
+}
+
+struct Checker<'a> {
+    contents: &'a str,
+    included_hosts: &'a [String],
+    diagnostics: Vec<(usize, Diagnostic)>,
+}
+
+impl Checker<'_> {
+    fn push(&mut self, span: Option<Range<usize>>, message: String) {
+        let offset = span
+            .map(|span| span.start)
+            .unwrap_or(0)
+            .min(self.contents.len());
+        let before = &self.contents[..offset];
+        let line = before.matches('\n').count() + 1;
+        let column = before
+            .rfind('\n')
+            .map_or(before, |newline| &before[newline + 1..])
+            .chars()
+            .count()
+            + 1;
+        self.diagnostics.push((
+            offset,
+            Diagnostic {
+                line,
+                column,
+                message,
+            },
+        ));
+    }
+
+    fn finish(mut self) -> Vec<Diagnostic> {
+        self.diagnostics.sort_by_key(|(offset, _)| *offset);
+        self.diagnostics
+            .into_iter()
+            .map(|(_, diagnostic)| diagnostic)
+            .collect()
+    }
+
+    fn unknown_keys(&mut self, table: &dyn TableLike, path: &str) {
+        let Some(known) = known_keys(path) else {
+            return;
+        };
+        for (name, item) in table.iter() {
+            let child = if path.is_empty() {
+                name.to_string()
+            } else {
+                format!("{path}.{name}")
+            };
+            if !known.contains(&name) {
+                let span = table
+                    .get_key_value(name)
+                    .and_then(|(key, item)| key.span().or_else(|| item.span()));
+                let location = if path.is_empty() {
+                    "at top level".to_string()
+                } else {
+                    format!("in [{path}]")
+                };
+                self.push(span, format!("unknown key `{name}` {location}"));
+                continue;
+            }
+            if let Some(table) = item.as_table_like() {
+                self.unknown_keys(table, &child);
+            }
+            for table in tables(item) {
+                self.unknown_keys(table, &child);
+            }
+        }
+    }
+
+    fn hosts(&mut self, root: &dyn TableLike) {
+        let mut seen: HashMap<String, usize> = HashMap::new();
+        let headers = root.get("hosts").and_then(Item::as_array_of_tables);
+        let hosts = root.get("hosts").map(tables).unwrap_or_default();
+        for (index, host) in hosts.into_iter().enumerate() {
+            let name = string(host, "name");
+            if let Some((name, span)) = &name {
+                let line = self.line_of(span.clone());
+                if let Some(first) = seen.get(*name) {
+                    self.push(
+                        Some(span.clone()),
+                        format!("duplicate host `{name}` (first defined on line {first})"),
+                    );
+                } else {
+                    seen.insert(name.to_string(), line);
+                }
+            }
+            let label = name
+                .as_ref()
+                .map_or("host".to_string(), |(name, _)| format!("host `{name}`"));
+            match host.get("targets") {
+                Some(targets) => {
+                    if targets.as_array().is_some_and(|targets| targets.is_empty()) {
+                        self.push(targets.span(), format!("{label} has an empty target list"));
+                    }
+                }
+                None if name.as_ref().is_some_and(|(name, _)| {
+                    self.included_hosts.iter().any(|host| host == name)
+                }) => {}
+                None => {
+                    let span = headers
+                        .and_then(|headers| headers.get(index))
+                        .and_then(|header| header.span())
+                        .or_else(|| span_of(host));
+                    self.push(span, format!("{label} has no targets"));
+                }
+            }
+        }
+    }
+
+    fn inventory(&mut self, root: &dyn TableLike) {
+        let mut seen: Vec<String> = Vec::new();
+        let headers = root.get("inventory").and_then(Item::as_array_of_tables);
+        let sources = root.get("inventory").map(tables).unwrap_or_default();
+        for (index, source) in sources.into_iter().enumerate() {
+            let header = headers
+                .and_then(|headers| headers.get(index))
+                .and_then(|header| header.span())
+                .or_else(|| span_of(source));
+            match string(source, "name") {
+                Some((name, span)) if name.trim().is_empty() => {
+                    self.push(Some(span), "inventory source name is empty".to_string());
+                }
+                Some((name, span)) => {
+                    if seen.iter().any(|seen| seen == name) {
+                        self.push(Some(span), format!("duplicate inventory source `{name}`"));
+                    }
+                    seen.push(name.to_string());
+                }
+                None => self.push(header.clone(), "inventory source needs a name".to_string()),
+            }
+            let command = source.get("command").is_some();
+            let file = source.get("file").is_some();
+            if command == file {
+                self.push(
+                    header,
+                    "inventory source needs exactly one of `command` or `file`".to_string(),
+                );
+            }
+            if let Some((format, span)) = string(source, "format")
+                && !matches!(format, "auto" | "json" | "toml" | "tailscale")
+            {
+                self.push(
+                    Some(span),
+                    format!(
+                        "unknown inventory format `{format}` (expected auto, json, toml or tailscale)"
+                    ),
+                );
+            }
+        }
+    }
+
+    fn tracked(&mut self, root: &dyn TableLike) {
+        if root.contains_key("inventory") {
+            return;
+        }
+        let mut names: Vec<String> = root
+            .get("hosts")
+            .map(tables)
+            .unwrap_or_default()
+            .into_iter()
+            .filter_map(|host| string(host, "name").map(|(name, _)| name.to_string()))
+            .chain(self.included_hosts.iter().cloned())
+            .collect();
+        let local = root.get("local").and_then(Item::as_table_like);
+        let local_enabled = local
+            .and_then(|local| local.get("enabled"))
+            .and_then(Item::as_bool)
+            .unwrap_or(true);
+        if local_enabled {
+            let name = local
+                .and_then(|local| string(local, "name"))
+                .map(|(name, _)| name.trim())
+                .filter(|name| !name.is_empty())
+                .unwrap_or("local");
+            names.push(name.to_string());
+        }
+        for section in ["tracked", "bookmarks"] {
+            for pane in root.get(section).map(tables).unwrap_or_default() {
+                if let Some((host, span)) = string(pane, "host")
+                    && !names.iter().any(|name| name == host)
+                {
+                    self.push(
+                        Some(span),
+                        format!("{section} pane references unknown host `{host}`"),
+                    );
+                }
+            }
+        }
+    }
+
+    fn colors(&mut self, root: &dyn TableLike) {
+        for host in root.get("hosts").map(tables).unwrap_or_default() {
+            self.color(host, "color");
+        }
+        if let Some(local) = root.get("local").and_then(Item::as_table_like) {
+            self.color(local, "color");
+        }
+        if let Some(palette) = root
+            .get("colors")
+            .and_then(Item::as_table_like)
+            .and_then(|colors| colors.get("default_host_palette"))
+            .and_then(Item::as_array)
+        {
+            for value in palette.iter() {
+                if let Some(name) = value.as_str()
+                    && model::parse_color(name).is_none()
+                {
+                    self.push(value.span(), invalid_color(name));
+                }
+            }
+        }
+    }
+
+    fn color(&mut self, table: &dyn TableLike, key: &str) {
+        if let Some((name, span)) = string(table, key)
+            && model::parse_color(name).is_none()
+        {
+            self.push(Some(span), invalid_color(name));
+        }
+    }
+
+    fn patterns(&mut self, root: &dyn TableLike) {
+        let Some(notifications) = root.get("notifications").and_then(Item::as_table_like) else {
+            return;
+        };
+        for key in ["error_patterns", "input_patterns"] {
+            let Some(patterns) = notifications.get(key).and_then(Item::as_array) else {
+                continue;
+            };
+            for value in patterns.iter() {
+                if let Some(pattern) = value.as_str()
+                    && let Err(err) = Regex::new(pattern)
+                {
+                    let reason = err.to_string();
+                    let reason = reason
+                        .lines()
+                        .last()
+                        .unwrap_or_default()
+                        .trim_start_matches("error: ");
+                    self.push(
+                        value.span(),
+                        format!("invalid regex in notifications.{key}: {reason}"),
+                    );
+                }
+            }
+        }
+    }
+
+    fn ranges(&mut self, root: &dyn TableLike) {
+        for (path, key, min, max) in RANGES {
+            let mut sections = vec![root];
+            for part in path.split('.') {
+                sections = sections
+                    .into_iter()
+                    .filter_map(|table| table.get(part))
+                    .flat_map(|item| match item.as_table_like() {
+                        Some(table) => vec![table],
+                        None => tables(item),
+                    })
+                    .collect();
+            }
+            for table in sections {
+                let Some(value) = table.get(key) else {
+                    continue;
+                };
+                if let Some(number) = value.as_integer()
+                    && (number < *min || number > *max)
+                {
+                    self.push(
+                        value.span(),
+                        format!("{path}.{key} = {number} is out of range ({min}..={max})"),
+                    );
+                }
+            }
+        }
+    }
+
+    fn line_of(&self, span: Range<usize>) -> usize {
+        self.contents[..span.start.min(self.contents.len())]
+            .matches('\n')
+            .count()
+            + 1
+    }
+}
+
+fn tables(item: &Item) -> Vec<&dyn TableLike> {
+    match item {
+        Item::ArrayOfTables(array) => array.iter().map(|table| table as &dyn TableLike).collect(),
+        Item::Value(Value::Array(array)) => array
+            .iter()
+            .filter_map(|value| value.as_inline_table().map(|table| table as &dyn TableLike))
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+fn string<'a>(table: &'a dyn TableLike, key: &str) -> Option<(&'a str, Range<usize>)> {
+    let item = table.get(key)?;
+    Some((item.as_str()?, item.span()?))
+}
+
+fn span_of(table: &dyn TableLike) -> Option<Range<usize>> {
+    table.iter().find_map(|(name, item)| {
+        table
+            .get_key_value(name)
+            .and_then(|(key, _)| key.span())
+            .or_else(|| item.span())
+    })
+}
+
+fn invalid_color(name: &str) -> String {
+    format!(
+        "invalid color `{name}` (expected black, red, green, yellow, blue, magenta, cyan, gray, darkgray, white or a light variant)"
+    )
+}
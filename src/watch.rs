@@ -0,0 +1,107 @@
+use crate::config::{self, Config};
+use crate::events::{EventTracker, FleetEvent};
+use crate::inventory;
+use crate::model::AppState;
+use crate::poller;
+use crate::ssh::HostResolver;
+use crate::statefile;
+use crate::notify::Router;
+use anyhow::{anyhow, Context, Result};
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{mpsc, Mutex};
+
+pub async fn run(mut config: Config, config_path: &Path, json: bool) -> Result<()> {
+    config::apply_local_host(&mut config);
+    for warning in &config.warnings {
+        eprintln!("{warning}");
+    }
+    for error in inventory::load_all(&mut config).await {
+        eprintln!("{error}");
+    }
+    if config.tracked.is_empty() {
+        return Err(anyhow!(
+            "No tracked panes configured. Run fleetmux to select panes first."
+        ));
+    }
+
+    let mut state = AppState::new(config.clone(), HashMap::new());
+    match statefile::load(config_path) {
+        Ok(saved) => saved.restore(&mut state),
+        Err(err) => eprintln!("{err:#}; starting with empty state"),
+    }
+    let mut tracker = EventTracker::new(&state);
+    let mut router = Router::new(&config, true)?;
+    let resolver = Arc::new(Mutex::new(HostResolver::new()));
+    let (update_tx, mut update_rx) = mpsc::channel(100);
+    let mut pollers = poller::start_pollers(&config, Arc::clone(&resolver), update_tx.clone());
+    let (inventory_tx, mut inventory_rx) = mpsc::channel(16);
+    let _inventory = inventory::start(&config, inventory_tx);
+    let mut tick = tokio::time::interval(Duration::from_millis(200));
+    let mut stdout = io::stdout();
+
+    let result = loop {
+        let events = tokio::select! {
+            maybe_update = update_rx.recv() => {
+                let Some(update) = maybe_update else { break Ok(()); };
+                if !pollers.is_current(&update) {
+                    continue;
+                }
+                let index = update.index;
+                if state.apply_update(update)
+                    && let Some(pane) = state.panes.get(index)
+                {
+                    router.observe_output(pane);
+                }
+                tracker.collect(&state, None)
+            }
+            Some(update) = inventory_rx.recv() => {
+                match update.result {
+                    Ok(hosts) => {
+                        if inventory::apply(&mut config, &update.source, hosts) {
+                            state.reconcile(config.clone(), HashMap::new());
+                            tracker = EventTracker::new(&state);
+                            pollers.update(&config).await;
+                        }
+                    }
+                    Err(err) => eprintln!("Inventory {}: {err:#}", update.source),
+                }
+                Vec::new()
+            }
+            _ = tick.tick() => {
+                let transitions = state.tick();
+                tracker.collect(&state, Some(&transitions))
+            }
+            _ = tokio::signal::ctrl_c() => break Ok(()),
+        };
+        for error in router.handle(&state, &events) {
+            eprintln!("{error}");
+        }
+        match write_events(&mut stdout, &events, json) {
+            Ok(()) => {}
+            Err(err) if err.kind() == io::ErrorKind::BrokenPipe => break Ok(()),
+            Err(err) => break Err(err).context("Unable to write events"),
+        }
+    };
+
+    pollers.stop().await;
+    result
+}
+
+fn write_events(out: &mut impl Write, events: &[FleetEvent], json: bool) -> io::Result<()> {
+    if events.is_empty() {
+        return Ok(());
+    }
+    for event in events {
+        if json {
+            let line = serde_json::to_string(event).map_err(io::Error::other)?;
+            writeln!(out, "{line}")?;
+        } else {
+            writeln!(out, "{} {}", event.ts(), event.describe())?;
+        }
+    }
+    out.flush()
+}
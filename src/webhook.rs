@@ -0,0 +1,443 @@
+use crate::config::WebhookConfig;
+use crate::dbus::Urgency;
+use crate::notify::{Notification, NotifyKind};
+use anyhow::{anyhow, Context, Result};
+use std::collections::VecDeque;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use tokio::sync::mpsc;
+
+const DEFAULT_TEMPLATE: &str = r#"{"text": "{{text}}"}"#;
+const RATE_WINDOW: Duration = Duration::from_secs(60);
+
+fn render(template: &str, event: &str, notification: &Notification) -> String {
+    let pane = notification.pane.as_ref();
+    let fields = [
+        ("text", notification.text()),
+        ("event", event.to_string()),
+        ("host", notification.host.clone()),
+        ("session", pane.map(|pane| pane.session.clone()).unwrap_or_default()),
+        ("window", pane.map(|pane| pane.window.to_string()).unwrap_or_default()),
+        ("pane_id", pane.map(|pane| pane.pane_id.clone()).unwrap_or_default()),
+        ("label", pane.and_then(|pane| pane.label.clone()).unwrap_or_default()),
+        ("target", notification.target.clone().unwrap_or_default()),
+        ("ts", notification.ts.clone()),
+    ];
+    // One pass, so placeholders inside pane output or titles stay literal.
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        rendered.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let field = after.find("}}").and_then(|end| {
+            fields
+                .iter()
+                .find(|(name, _)| *name == &after[..end])
+                .map(|(_, value)| (end, value))
+        });
+        match field {
+            Some((end, value)) => {
+                rendered.push_str(&json_escape(value));
+                rest = &after[end + 2..];
+            }
+            None => {
+                rendered.push_str("{{");
+                rest = after;
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+struct Hook {
+    name: String,
+    kinds: Vec<NotifyKind>,
+    template: String,
+    rate_limit_per_min: u32,
+    sent: VecDeque<Instant>,
+    dropped: usize,
+    tx: mpsc::UnboundedSender<String>,
+}
+
+pub struct Webhooks {
+    hooks: Vec<Hook>,
+    errors_tx: mpsc::UnboundedSender<String>,
+    errors_rx: mpsc::UnboundedReceiver<String>,
+}
+
+impl Webhooks {
+    pub fn new(configs: &[WebhookConfig]) -> Result<Self> {
+        let (errors_tx, errors_rx) = mpsc::unbounded_channel();
+        let mut hooks = Vec::new();
+        for config in configs {
+            let name = config.display_name();
+            let kinds = parse_kinds(config).with_context(|| format!("Invalid webhook {name}"))?;
+            let template = check_template(config).with_context(|| format!("Invalid webhook {name}"))?;
+            let (tx, rx) = mpsc::unbounded_channel();
+            tokio::spawn(run_worker(config.clone(), rx, errors_tx.clone()));
+            hooks.push(Hook {
+                name,
+                kinds,
+                template,
+                rate_limit_per_min: config.rate_limit_per_min,
+                sent: VecDeque::new(),
+                dropped: 0,
+                tx,
+            });
+        }
+        Ok(Self {
+            hooks,
+            errors_tx,
+            errors_rx,
+        })
+    }
+
+    pub fn dispatch(&mut self, notification: &Notification) {
+        let now = Instant::now();
+        for hook in &mut self.hooks {
+            if !hook.kinds.contains(&notification.kind) {
+                continue;
+            }
+            while hook
+                .sent
+                .front()
+                .is_some_and(|at| now.duration_since(*at) >= RATE_WINDOW)
+            {
+                hook.sent.pop_front();
+            }
+            if hook.rate_limit_per_min > 0 && hook.sent.len() >= hook.rate_limit_per_min as usize {
+                hook.dropped += 1;
+                let _ = self.errors_tx.send(format!(
+                    "Webhook {} rate limited ({} dropped)",
+                    hook.name, hook.dropped
+                ));
+                continue;
+            }
+            hook.sent.push_back(now);
+            let _ = hook
+                .tx
+                .send(render(&hook.template, notification.kind.name(), notification));
+        }
+    }
+
+    pub fn take_errors(&mut self) -> Vec<String> {
+        let mut errors = Vec::new();
+        while let Ok(error) = self.errors_rx.try_recv() {
+            errors.push(error);
+        }
+        errors
+    }
+}
+
+pub async fn send_test(config: &WebhookConfig) -> Result<()> {
+    let template = check_template(config)?;
+    let notification = Notification {
+        kind: NotifyKind::Done,
+        title: "FleetMux".to_string(),
+        body: "Test notification from fleetmux".to_string(),
+        urgency: Urgency::Normal,
+        host: "fleetmux".to_string(),
+        pane: None,
+        target: None,
+        ts: chrono::Local::now().to_rfc3339_opts(chrono::SecondsFormat::Millis, false),
+    };
+    deliver(config, &render(&template, "test", &notification)).await
+}
+
+async fn run_worker(
+    config: WebhookConfig,
+    mut rx: mpsc::UnboundedReceiver<String>,
+    errors_tx: mpsc::UnboundedSender<String>,
+) {
+    while let Some(payload) = rx.recv().await {
+        if let Err(err) = deliver(&config, &payload).await {
+            let _ = errors_tx.send(format!("Webhook {} failed: {err}", config.display_name()));
+        }
+    }
+}
+
+async fn deliver(config: &WebhookConfig, payload: &str) -> Result<()> {
+    let mut attempt = 0;
+    loop {
+        match post(config, payload).await {
+            Ok(()) => return Ok(()),
+            Err(PostError::Fatal(err)) => return Err(err),
+            Err(PostError::Retry(err)) => {
+                if attempt >= config.retries {
+                    return Err(err);
+                }
+                tokio::time::sleep(Duration::from_secs(1 << attempt.min(5))).await;
+                attempt += 1;
+            }
+        }
+    }
+}
+
+enum PostError {
+    Retry(anyhow::Error),
+    Fatal(anyhow::Error),
+}
+
+async fn post(config: &WebhookConfig, payload: &str) -> std::result::Result<(), PostError> {
+    let timeout = Duration::from_secs(config.timeout_sec.max(1));
+    let mut cmd = Command::new("curl");
+    cmd.arg("-sS")
+        .arg("-o")
+        .arg("/dev/null")
+        .arg("-w")
+        .arg("%{http_code}")
+        .arg("-X")
+        .arg("POST")
+        .arg("--max-time")
+        .arg(timeout.as_secs().to_string())
+        .arg("--config")
+        .arg("-")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true);
+
+    let mut child = cmd
+        .spawn()
+        .map_err(|err| PostError::Fatal(anyhow!("Unable to run curl: {err}")))?;
+    if let Some(mut stdin) = child.stdin.take() {
+        let _ = stdin.write_all(curl_config(config, payload).as_bytes()).await;
+    }
+    let output = tokio::time::timeout(timeout + Duration::from_secs(2), child.wait_with_output())
+        .await
+        .map_err(|_| PostError::Retry(anyhow!("timed out after {}s", timeout.as_secs())))?
+        .map_err(|err| PostError::Retry(anyhow!("curl failed: {err}")))?;
+
+    let code: u16 = String::from_utf8_lossy(&output.stdout).trim().parse().unwrap_or(0);
+    match code {
+        200..=299 => Ok(()),
+        0 => {
+            let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+            Err(PostError::Retry(anyhow!("{stderr}")))
+        }
+        408 | 429 | 500..=599 => Err(PostError::Retry(anyhow!("HTTP {code}"))),
+        _ => Err(PostError::Fatal(anyhow!("HTTP {code}"))),
+    }
+}
+
+// Headers and the URL often carry tokens, so they go to curl on stdin instead of argv.
+fn curl_config(config: &WebhookConfig, payload: &str) -> String {
+    let mut lines = vec![("header", "Content-Type: application/json")];
+    lines.extend(config.headers.iter().map(|header| ("header", header.as_str())));
+    lines.push(("url", config.url.as_str()));
+    lines.push(("data-binary", payload));
+    lines
+        .into_iter()
+        .map(|(option, value)| format!("{option} = \"{}\"\n", curl_quote(value)))
+        .collect()
+}
+
+fn curl_quote(value: &str) -> String {
+    let mut quoted = String::with_capacity(value.len());
+    for ch in value.chars() {
+        match ch {
+            '\\' => quoted.push_str("\\\\"),
+            '"' => quoted.push_str("\\\""),
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            '\t' => quoted.push_str("\\t"),
+            ch => quoted.push(ch),
+        }
+    }
+    quoted
+}
+
+fn parse_kinds(config: &WebhookConfig) -> Result<Vec<NotifyKind>> {
+    if config.url.trim().is_empty() {
+        return Err(anyhow!("url is required"));
+    }
+    NotifyKind::parse_list(&config.events)
+}
+
+fn check_template(config: &WebhookConfig) -> Result<String> {
+    let template = config
+        .template
+        .clone()
+        .filter(|template| !template.trim().is_empty())
+        .unwrap_or_else(|| DEFAULT_TEMPLATE.to_string());
+    let sample = Notification {
+        kind: NotifyKind::Done,
+        title: "Sample".to_string(),
+        body: "sample \"text\"\\".to_string(),
+        urgency: Urgency::Normal,
+        host: String::new(),
+        pane: None,
+        target: None,
+        ts: String::new(),
+    };
+    serde_json::from_str::<serde_json::Value>(&render(&template, "done", &sample))
+        .context("template does not render valid JSON")?;
+    Ok(template)
+}
+
+fn json_escape(value: &str) -> String {
+    let quoted = serde_json::to_string(value).unwrap_or_default();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::mpsc as std_mpsc;
+
+    struct Request {
+        headers: Vec<String>,
+        body: String,
+    }
+
+    // Answers each connection with the next status in `statuses`.
+    fn stub(statuses: Vec<u16>) -> (String, std_mpsc::Receiver<Request>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        let (tx, rx) = std_mpsc::channel();
+        std::thread::spawn(move || {
+            for status in statuses {
+                let Ok((stream, _)) = listener.accept() else {
+                    return;
+                };
+                let mut reader = BufReader::new(stream);
+                let mut headers = Vec::new();
+                let mut length = 0;
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end().to_string();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if let Some(value) = line.to_ascii_lowercase().strip_prefix("content-length:") {
+                        length = value.trim().parse().unwrap();
+                    }
+                    headers.push(line);
+                }
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let _ = tx.send(Request {
+                    headers,
+                    body: String::from_utf8(body).unwrap(),
+                });
+                let _ = write!(
+                    reader.get_mut(),
+                    "HTTP/1.1 {status} Status\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                );
+            }
+        });
+        (url, rx)
+    }
+
+    fn config(url: String) -> WebhookConfig {
+        WebhookConfig {
+            url,
+            events: vec!["done".to_string()],
+            template: Some(r#"{"text": "{{text}}", "host": "{{host}}"}"#.to_string()),
+            headers: vec!["Authorization: Bearer s3cret \"quoted\"".to_string()],
+            retries: 1,
+            ..WebhookConfig::default()
+        }
+    }
+
+    fn notification() -> Notification {
+        Notification {
+            kind: NotifyKind::Done,
+            title: "Pane done".to_string(),
+            body: "build \"finished\"\nok".to_string(),
+            urgency: Urgency::Normal,
+            host: "buildbox".to_string(),
+            pane: None,
+            target: None,
+            ts: String::new(),
+        }
+    }
+
+    #[tokio::test]
+    async fn posts_rendered_json_with_headers() {
+        let (url, requests) = stub(vec![200]);
+        let config = config(url);
+        let payload = render(&check_template(&config).unwrap(), "done", &notification());
+        deliver(&config, &payload).await.unwrap();
+
+        let request = requests.recv().unwrap();
+        let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+        assert_eq!(body["host"], "buildbox");
+        assert_eq!(body["text"], notification().text());
+        assert!(request
+            .headers
+            .iter()
+            .any(|header| header == "Authorization: Bearer s3cret \"quoted\""));
+        assert!(request
+            .headers
+            .iter()
+            .any(|header| header == "Content-Type: application/json"));
+    }
+
+    #[test]
+    fn leaves_placeholders_in_values_alone() {
+        let notification = Notification {
+            body: "echo {{host}} {{target}}".to_string(),
+            target: Some("ops".to_string()),
+            ..notification()
+        };
+        let rendered = render(
+            "{{text}}|{{host}}|{{target}}|{{unknown}}|{{",
+            "done",
+            &notification,
+        );
+        assert_eq!(
+            rendered,
+            format!(
+                "{}|buildbox|ops|{{{{unknown}}}}|{{{{",
+                json_escape(&notification.text())
+            )
+        );
+    }
+
+    #[tokio::test]
+    async fn retries_after_server_error() {
+        let (url, requests) = stub(vec![503, 200]);
+        deliver(&config(url), "{}").await.unwrap();
+        assert_eq!(requests.try_iter().count(), 2);
+    }
+
+    #[tokio::test]
+    async fn gives_up_on_client_error() {
+        let (url, requests) = stub(vec![400, 200]);
+        assert!(deliver(&config(url), "{}").await.is_err());
+        assert_eq!(requests.try_iter().count(), 1);
+    }
+
+    #[tokio::test]
+    async fn drops_notifications_over_the_rate_limit() {
+        let (url, requests) = stub(vec![200, 200]);
+        let mut webhooks = Webhooks::new(&[WebhookConfig {
+            rate_limit_per_min: 1,
+            ..config(url)
+        }])
+        .unwrap();
+        webhooks.dispatch(&notification());
+        webhooks.dispatch(&notification());
+
+        let received = tokio::task::spawn_blocking(move || {
+            let first = requests.recv_timeout(Duration::from_secs(10)).is_ok();
+            let second = requests.recv_timeout(Duration::from_millis(500)).is_ok();
+            (first, second)
+        })
+        .await
+        .unwrap();
+        assert_eq!(received, (true, false));
+        assert_eq!(webhooks.hooks[0].dropped, 1);
+        assert!(webhooks
+            .take_errors()
+            .iter()
+            .any(|error| error.contains("rate limited (1 dropped)")));
+    }
+}